@@ -0,0 +1,60 @@
+use std::borrow::Cow;
+
+use serde::de::Deserialize;
+
+use crate::path::{IntoPath, Path};
+use crate::reader::{ItemReader, JsonItError};
+use crate::scanner::Container;
+use crate::source::InMemory;
+
+/// Holds data in order to parse a document already in memory, like a `&[u8]`, a `&str` or a memory map, in UTF-8.
+/// The items are deserialized in place, so they can borrow from the document, like `&'de str` fields:
+/// a borrowed string can not hold escape sequences, use a `Cow<'de, str>` when it might.
+/// Should be created using [JsonSliceIterator::new]
+///
+/// ```
+/// # use serde::Deserialize;
+/// # use jsonit::JsonSliceIterator;
+/// #[derive(Deserialize)]
+/// struct User<'a> {
+///     name: &'a str,
+/// }
+///
+/// let data = r#"{"users": [{"name": "a"}, {"name": "b"}]}"#;
+/// let names = JsonSliceIterator::<User>::new(data, "users")
+///     .map(|user| user.map(|user| user.name))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(names, ["a", "b"]);
+/// ```
+pub struct JsonSliceIterator<'de, 'a, O> {
+	inner: ItemReader<InMemory<&'de [u8]>>,
+	path: Cow<'a, Path>,
+	output_type: std::marker::PhantomData<O>,
+}
+
+impl<'de, 'a, O: Deserialize<'de>> JsonSliceIterator<'de, 'a, O> {
+	/// Creates a new [JsonSliceIterator] from a document in memory and the [Path] of the array to stream,
+	/// which can also be given as a string to parse
+	pub fn new<D: AsRef<[u8]> + ?Sized>(data: &'de D, path_to_look_for: impl IntoPath<'a>) -> Self {
+		let (inner, path) = ItemReader::with_path(InMemory::new(data.as_ref()), Container::Array, path_to_look_for);
+		Self {
+			inner,
+			path,
+			output_type: std::marker::PhantomData,
+		}
+	}
+
+	/// Gives no item, instead of [JsonItError::PathNotFound], when the path is missing from the document
+	pub fn missing_as_empty(mut self) -> Self {
+		self.inner.missing_as_empty();
+		self
+	}
+}
+
+impl<'de, O: Deserialize<'de>> Iterator for JsonSliceIterator<'de, '_, O> {
+	type Item = Result<O, JsonItError>;
+	fn next(&mut self) -> Option<Self::Item> {
+		self.inner.next_at(&self.path)
+	}
+}
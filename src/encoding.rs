@@ -0,0 +1,252 @@
+use std::io::{self, Read};
+
+use crate::source::BUFFER_SIZE;
+
+/// The encodings of a JSON document, detected from its first bytes as in RFC 4627
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum Encoding {
+	Utf8,
+	Utf16Le,
+	Utf16Be,
+	Utf32Le,
+	Utf32Be,
+}
+
+impl Encoding {
+	/// Detects the encoding from the first bytes, with a byte order mark or from the position of the zeros,
+	/// as the first character of a JSON document is ASCII. Also gives the length of the byte order mark
+	fn detect(head: &[u8]) -> (Self, usize) {
+		match head {
+			[0xEF, 0xBB, 0xBF, ..] => (Self::Utf8, 3),
+			[0x00, 0x00, 0xFE, 0xFF, ..] => (Self::Utf32Be, 4),
+			[0xFF, 0xFE, 0x00, 0x00, ..] => (Self::Utf32Le, 4),
+			[0xFE, 0xFF, ..] => (Self::Utf16Be, 2),
+			[0xFF, 0xFE, ..] => (Self::Utf16Le, 2),
+			[0x00, 0x00, 0x00, _, ..] => (Self::Utf32Be, 0),
+			[_, 0x00, 0x00, 0x00, ..] => (Self::Utf32Le, 0),
+			[0x00, _, ..] => (Self::Utf16Be, 0),
+			[_, 0x00, ..] => (Self::Utf16Le, 0),
+			_ => (Self::Utf8, 0),
+		}
+	}
+
+	fn unit_size(&self) -> usize {
+		match self {
+			Self::Utf8 => 1,
+			Self::Utf16Le | Self::Utf16Be => 2,
+			Self::Utf32Le | Self::Utf32Be => 4,
+		}
+	}
+}
+
+fn invalid(message: &'static str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A [Read] giving a document in UTF-8, without byte order mark, whatever the encoding of the one it reads
+pub(crate) struct Decoder<R> {
+	reader: R,
+	/// Detected once the first bytes are read
+	encoding: Option<Encoding>,
+	/// The first bytes, read to detect the encoding but not decoded yet
+	head: [u8; 4],
+	head_range: (usize, usize),
+	/// The bytes read but not decoded yet, in another encoding than UTF-8, allocated once needed
+	raw: Vec<u8>,
+	raw_range: (usize, usize),
+	/// How many bytes to read at once from the reader, into [Decoder::raw]
+	chunk: usize,
+	/// A character decoded but not returned yet
+	out: [u8; 4],
+	out_range: (usize, usize),
+}
+
+impl<R: Read> Decoder<R> {
+	pub(crate) fn new(reader: R) -> Self {
+		Self {
+			reader,
+			encoding: None,
+			head: [0; 4],
+			head_range: (0, 0),
+			raw: vec![],
+			raw_range: (0, 0),
+			chunk: BUFFER_SIZE,
+			out: [0; 4],
+			out_range: (0, 0),
+		}
+	}
+
+	/// Never reads ahead of the bytes it gives, for readers which can block waiting for the next ones
+	pub(crate) fn unbuffered(reader: R) -> Self {
+		Self {
+			chunk: 1,
+			..Self::new(reader)
+		}
+	}
+
+	fn detect(&mut self) -> io::Result<Encoding> {
+		let mut read = 0;
+		while read < self.head.len() {
+			match self.reader.read(&mut self.head[read..]) {
+				Ok(0) => break,
+				Ok(n) => read += n,
+				Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+				Err(e) => return Err(e),
+			}
+		}
+		let (encoding, bom) = Encoding::detect(&self.head[..read]);
+		self.head_range = (bom, read);
+		self.encoding = Some(encoding);
+		Ok(encoding)
+	}
+
+	fn next_byte(&mut self) -> io::Result<Option<u8>> {
+		let (start, end) = self.head_range;
+		if start < end {
+			self.head_range.0 += 1;
+			return Ok(Some(self.head[start]));
+		}
+		let (start, end) = self.raw_range;
+		if start < end {
+			self.raw_range.0 += 1;
+			return Ok(Some(self.raw[start]));
+		}
+		if self.raw.len() < self.chunk {
+			self.raw.resize(self.chunk, 0);
+		}
+		loop {
+			match self.reader.read(&mut self.raw[..self.chunk]) {
+				Ok(0) => return Ok(None),
+				Ok(read) => {
+					self.raw_range = (1, read);
+					return Ok(Some(self.raw[0]));
+				}
+				Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+				Err(e) => return Err(e),
+			}
+		}
+	}
+
+	/// Reads the next code unit, [None] at the end of the document
+	fn next_unit(&mut self, encoding: Encoding) -> io::Result<Option<u32>> {
+		let mut bytes = [0_u8; 4];
+		let size = encoding.unit_size();
+		for (i, slot) in bytes.iter_mut().take(size).enumerate() {
+			match self.next_byte()? {
+				Some(byte) => *slot = byte,
+				None if i == 0 => return Ok(None),
+				None => return Err(invalid("the document ends in the middle of a character")),
+			}
+		}
+		Ok(Some(match encoding {
+			Encoding::Utf8 => u32::from(bytes[0]),
+			Encoding::Utf16Le => u32::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+			Encoding::Utf16Be => u32::from(u16::from_be_bytes([bytes[0], bytes[1]])),
+			Encoding::Utf32Le => u32::from_le_bytes(bytes),
+			Encoding::Utf32Be => u32::from_be_bytes(bytes),
+		}))
+	}
+
+	/// Decodes the next character, [None] at the end of the document
+	fn next_char(&mut self, encoding: Encoding) -> io::Result<Option<char>> {
+		let Some(unit) = self.next_unit(encoding)? else {
+			return Ok(None);
+		};
+		let code = match unit {
+			0xD800..=0xDBFF if matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be) => {
+				match self.next_unit(encoding)? {
+					Some(low @ 0xDC00..=0xDFFF) => 0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00),
+					_ => return Err(invalid("a UTF-16 surrogate is not followed by another one")),
+				}
+			}
+			unit => unit,
+		};
+		char::from_u32(code)
+			.map(Some)
+			.ok_or_else(|| invalid("invalid character"))
+	}
+}
+
+impl<R: Read> Read for Decoder<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let encoding = match self.encoding {
+			Some(encoding) => encoding,
+			None => self.detect()?,
+		};
+		if encoding == Encoding::Utf8 {
+			let (start, end) = self.head_range;
+			if start < end {
+				let n = buf.len().min(end - start);
+				buf[..n].copy_from_slice(&self.head[start..start + n]);
+				self.head_range.0 += n;
+				return Ok(n);
+			}
+			return self.reader.read(buf);
+		}
+		let mut written = 0;
+		while written < buf.len() {
+			let (start, end) = self.out_range;
+			if start < end {
+				let n = (buf.len() - written).min(end - start);
+				buf[written..written + n].copy_from_slice(&self.out[start..start + n]);
+				self.out_range.0 += n;
+				written += n;
+				continue;
+			}
+			match self.next_char(encoding)? {
+				Some(c) => self.out_range = (0, c.encode_utf8(&mut self.out).len()),
+				None => break,
+			}
+		}
+		Ok(written)
+	}
+}
+
+/// Gives the bytes one at a time, without reading ahead of them when [Decoder::unbuffered]
+impl<R: Read> Iterator for Decoder<R> {
+	type Item = io::Result<u8>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let mut byte = [0_u8; 1];
+		match self.read(&mut byte) {
+			Ok(0) => None,
+			Ok(_) => Some(Ok(byte[0])),
+			Err(e) => Some(Err(e)),
+		}
+	}
+}
+
+/// A [Read] over an iterator of bytes, only taking the ones it gives
+pub(crate) struct IterRead<I> {
+	bytes: I,
+	/// An error given after some bytes, returned by the next read
+	error: Option<io::Error>,
+}
+
+impl<I> IterRead<I> {
+	pub(crate) fn new(bytes: I) -> Self {
+		Self { bytes, error: None }
+	}
+}
+
+impl<I: Iterator<Item = io::Result<u8>>> Read for IterRead<I> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		if let Some(e) = self.error.take() {
+			return Err(e);
+		}
+		let mut written = 0;
+		for slot in buf.iter_mut() {
+			match self.bytes.next() {
+				Some(Ok(c)) => *slot = c,
+				Some(Err(e)) if written == 0 => return Err(e),
+				Some(Err(e)) => {
+					self.error = Some(e);
+					break;
+				}
+				None => break,
+			}
+			written += 1;
+		}
+		Ok(written)
+	}
+}
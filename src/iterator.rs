@@ -1,40 +1,130 @@
 use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
 
 
-use crate::utils::{make_prefix, compare_stack};
+use std::borrow::Borrow;
+use std::io::{self, Read};
+use std::marker::PhantomData;
 
-fn fold_and_parse<T>(iterator: impl Iterator<Item = Delimiter>) -> impl Iterator<Item = serde_json::Result<T>>
-where
-	T: DeserializeOwned,
-{
-	let mut v: Vec<u8> = vec![];
+use crate::encoding::{Decoder, IterRead};
+use crate::location::Position;
+use crate::path::{IntoPath, Path};
+use crate::reader::{Expect, JsonItError};
+use crate::scanner::{is_scalar_byte, is_whitespace, Event, Scanner, Unexpected, ValueKind};
+use crate::source::{deserialize_delimited, deserialize_token};
+
+/// Deserializes the items while their bytes come, one at a time
+struct Items<I, T> {
+	delimiters: I,
+	/// The array whose items are read, for errors
+	container: Path,
+	/// Set after a malformed item, as we can not know where the next one starts
+	ended: bool,
+	output_type: PhantomData<T>,
+}
+
+impl<I: Iterator<Item = Delimiter>, T: DeserializeOwned> Iterator for Items<I, T> {
+	type Item = Result<T, JsonItError>;
 
-	iterator.filter_map(move |e| match e {
-		Delimiter::Item(e) => {
-			v.push(e);
-			None
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.ended {
+			return None;
 		}
-		Delimiter::End(e) => {
-			v.push(e.get_end());
-			let r = Some(serde_json::from_slice::<T>(&v));
-			v.clear();
-			r
+		loop {
+			match self.delimiters.next()? {
+				Delimiter::Start(e, first, index) => {
+					let mut bytes = ItemBytes {
+						delimiters: &mut self.delimiters,
+						pending: Some(e.get_start()),
+						position: first,
+						ended: false,
+						error: None,
+					};
+					// where serde stopped, before the rest of the item is skipped
+					let (item, stopped) = match e {
+						StructType::Scalar(_) => {
+							let mut token = Vec::new();
+							let _ = bytes.read_to_end(&mut token);
+							let (item, stopped, whole) = deserialize_token(&token, first);
+							// a malformed literal can not be skipped to read the next items
+							self.ended = !whole;
+							(item, stopped)
+						}
+						_ => deserialize_delimited(&mut bytes, first),
+					};
+					let _ = io::copy(&mut bytes, &mut io::sink());
+					if let Some(e) = bytes.error {
+						return Some(Err(e));
+					}
+					// only a value of the wrong shape lets us go on
+					self.ended |= item.as_ref().is_err_and(|error| !error.is_data());
+					return Some(item.map_err(|error| {
+						let location = stopped.locate(Some(index), self.container.clone());
+						JsonItError::SerdeError { error, location }
+					}));
+				}
+				Delimiter::Container(path) => self.container = path,
+				Delimiter::Error(e) => return Some(Err(e)),
+				// the stream ends before
+				Delimiter::Stop => return None,
+				// the bytes of the items which are not selected
+				Delimiter::Skip | Delimiter::Item(_) | Delimiter::End(..) => {}
+			}
 		}
-		// should never arrive here
-		Delimiter::Stop => panic!("Hum, we should never be here, got stop"),
-		// should never arrive here
-		Delimiter::Skip => None,
-		Delimiter::Start(e) => {
-			v.push(e.get_start());
-			None
+	}
+}
+
+/// A [Read] giving the bytes of the current item, from its delimiters, then ending
+struct ItemBytes<'i, I> {
+	delimiters: &'i mut I,
+	/// A byte to give before reading the next delimiter: the first or the last one of the item
+	pending: Option<u8>,
+	/// The position of the last byte given, or pending
+	position: Position,
+	ended: bool,
+	/// The error ending the stream in the middle of the item
+	error: Option<JsonItError>,
+}
+
+impl<I: Iterator<Item = Delimiter>> Read for ItemBytes<'_, I> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let mut read = 0;
+		while read < buf.len() {
+			if let Some(c) = self.pending.take() {
+				buf[read] = c;
+				read += 1;
+				continue;
+			}
+			if self.ended || self.error.is_some() {
+				break;
+			}
+			match self.delimiters.next() {
+				Some(Delimiter::Item(c)) => {
+					buf[read] = c;
+					read += 1;
+					self.position.advance(c);
+				}
+				Some(Delimiter::End(e)) => {
+					self.pending = Some(e.get_end());
+					self.position.advance(e.get_end());
+					self.ended = true;
+				}
+				Some(Delimiter::Error(e)) => self.error = Some(e),
+				Some(_) => {}
+				None => break,
+			}
 		}
-	})
+		Ok(read)
+	}
 }
 
 #[derive(PartialEq, Debug)]
 enum StructType {
 	Map,
 	Array,
+	String,
+	/// A number or a literal, starting with this byte, or followed by it at its end
+	Scalar(u8),
 }
 
 impl StructType {
@@ -42,58 +132,64 @@ impl StructType {
 		match self {
 			Self::Array => b'[',
 			Self::Map => b'{',
+			Self::String => b'"',
+			Self::Scalar(c) => *c,
 		}
 	}
+	/// Numbers and literals end with the byte after them, which is not part of the item
+	/// but tells serde where they end
 	fn get_end(&self) -> u8 {
 		match self {
 			Self::Array => b']',
 			Self::Map => b'}',
+			Self::String => b'"',
+			Self::Scalar(c) => *c,
 		}
 	}
 }
-#[derive(PartialEq, Debug)]
+#[derive(Debug)]
 enum Delimiter {
 	Stop,
 	Item(u8),
 	End(StructType),
 	Skip,
-	Start(StructType),
-}
-
-#[derive(PartialEq, Debug, Copy, Clone)]
-enum ParseValueType {
-	String,
-	Number,
-	Null,
-	Map,
-	Array,
-}
-
-#[derive(PartialEq, Debug)]
-enum State {
-	ParseObjectKey,
-	ParseObject,
-	ParseValue(ParseValueType),
-	ExpectValue,
-	/// We expect ":" or whitespace
-	ExpectPoints,
-	None,
+	/// Starts an array at this path
+	Container(Path),
+	/// Starts an item, at the position of its first byte and at this index in the array
+	Start(StructType, Position, usize),
+	/// The rest of the stream is skipped after it
+	Error(JsonItError),
 }
 
 const DEBUG: bool = false;
 
-// we split the prefix at the beginning into sub parts
+/// Only keeps the items selected by the path, counting them
+fn select(delimiter: Delimiter, path: &Path, many: bool, item_index: &mut usize, selected: &mut bool) -> Delimiter {
+	match delimiter {
+		Delimiter::Start(..) => {
+			if !many && path.is_past_items(*item_index) {
+				return Delimiter::Stop;
+			}
+			*selected = path.selects_item(*item_index);
+		}
+		Delimiter::End(..) => *item_index += 1,
+		Delimiter::Item(_) => {}
+		_ => return delimiter,
+	}
+	if *selected {
+		delimiter
+	} else {
+		Delimiter::Skip
+	}
+}
 
-/// will only support the stream loading of an array of object under a object key chain, like "a.b.c"
-/// does not support delimiting a struct of type array for now
-fn iter_delimiters(
-	iterator: impl Iterator<Item = u8> + 'static,
-	prefix: Vec<u8>,
-) -> impl Iterator<Item = Delimiter> + 'static {
-	// in order to know where we are in the object
-	let mut key_stack: Vec<Vec<u8>> = vec![];
-	// the current key where we parse the value
-	let mut current_key: Vec<u8> = vec![];
+/// will only support the stream loading of an array of object at a [Path].
+/// With wildcards, like "pages.*.items", the items of every matching array are returned one after the other
+fn iter_delimiters(iterator: impl Iterator<Item = io::Result<u8>>, path: Path) -> impl Iterator<Item = Delimiter> {
+	// in order to know where we are in the document
+	let mut scanner = Scanner::new();
+	// if there can be other arrays to return once the current one is done
+	let many = path.matches_many();
 
 	// if we should be currently returning items
 	let mut in_key = false;
@@ -102,211 +198,302 @@ fn iter_delimiters(
 	// if we have started returning values
 	// can maybe optimized away later
 	let mut started = false;
-	// keeps state of the parsing
-	let mut state = State::None;
-	// prevents rebuilding the key stack without rebuilding it
-	let mut stack_dirty = false;
-	// Keeps state if the next character is escaped
+	// position of the current item in the array, and if it is selected by the path
+	let mut item_index = 0;
+	let mut selected = true;
+	// brackets in strings are not delimiters
+	let mut in_string = false;
 	let mut escape = false;
+	// if the current item is a number or a literal
+	let mut in_scalar = false;
+	// what can come next in the array, between its items
+	let mut expect = Expect::First;
+	// if an array designated by the path was found
+	let mut found = false;
+	// once set, the stream ends after returning this delimiter
+	let mut last = None;
+	let mut position = Position::default();
 
 	iterator
+		// marks the end of the document
+		.map(Some)
+		.chain(std::iter::once(None))
 		.map(move |s| {
-			let c = s;
+			if let Some(delimiter) = last.take() {
+				// nothing comes after an error
+				if let Delimiter::Error(_) = delimiter {
+					last = Some(Delimiter::Stop);
+				}
+				return delimiter;
+			}
+			let c = match s {
+				Some(Ok(c)) => c,
+				Some(Err(error)) => {
+					last = Some(Delimiter::Stop);
+					return Delimiter::Error(JsonItError::IoError {
+						error,
+						location: position.locate(in_key.then_some(item_index), scanner.path()),
+					});
+				}
+				None if in_key => {
+					return Delimiter::Error(JsonItError::IoError {
+						error: io::ErrorKind::UnexpectedEof.into(),
+						location: position.locate(Some(item_index), scanner.path()),
+					})
+				}
+				None if found => return Delimiter::Stop,
+				None => {
+					return Delimiter::Error(JsonItError::PathNotFound {
+						path: path.clone(),
+						location: position.locate(None, scanner.path()),
+					})
+				}
+			};
+			position.advance(c);
 
 			// not pretty
 			if DEBUG {
-				println!(
-					"| {} | key {} | state {:?} | array nesting {}",
-					c,
-					compare_stack(&key_stack, &prefix),
-					&state,
-					array_nesting
-				);
+				println!("| {} | in key {} | array nesting {}", c, in_key, array_nesting);
 			}
 
-			// avoid testing the key many times
-			if stack_dirty && compare_stack(&key_stack, &prefix) {
-				stack_dirty = false;
-				in_key = true;
-				// array_nesting = 1;
-			}
-
-			// if we are in the searched key
+			// if we are in the searched array
 			// TODO: skip useless characters maybe
 			if in_key {
-				if c == b'[' {
-					array_nesting += 1;
-					if object_nesting == 0 && array_nesting == 2 {
-						started = true;
-						return Delimiter::Start(StructType::Array);
+				// the end of a number or a literal is only known with the byte after it
+				let mut scalar_end = None;
+				if in_scalar {
+					if is_scalar_byte(c) {
+						return if selected { Delimiter::Item(c) } else { Delimiter::Skip };
 					}
+					in_scalar = false;
+					started = false;
+					// the byte is not part of the item
+					scalar_end = Some(select(
+						Delimiter::End(StructType::Scalar(c)),
+						&path,
+						many,
+						&mut item_index,
+						&mut selected,
+					));
 				}
 
-				if c == b']' {
-					array_nesting -= 1;
-
-					if object_nesting == 0 && array_nesting == 1 {
-						started = false;
-						return Delimiter::End(StructType::Array);
+				let delimiter = 'item: {
+					let at_item_level = object_nesting == 0 && array_nesting == 1;
+					if in_string {
+						in_string = escape || c != b'"';
+						escape = !escape && c == b'\\';
+						if !in_string && at_item_level {
+							started = false;
+							break 'item Delimiter::End(StructType::String);
+						}
+						break 'item if started { Delimiter::Item(c) } else { Delimiter::Skip };
 					}
-
-					// end of parsing, skip the rest of the stream
-					if array_nesting == 0 {
-						in_key = false;
-						return Delimiter::Stop;
+					// between the items, only a separator, an item or the end of the array can come
+					if at_item_level {
+						match c {
+							w if is_whitespace(w) => break 'item Delimiter::Skip,
+							b',' if expect == Expect::After => {
+								expect = Expect::Next;
+								break 'item Delimiter::Skip;
+							}
+							b']' if expect != Expect::Next => {}
+							b'{' | b'[' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n' if expect != Expect::After => {
+								expect = Expect::After;
+							}
+							c => {
+								// the item just read, or the one which should come
+								let item = match expect {
+									Expect::After => item_index.saturating_sub(1),
+									_ => item_index,
+								};
+								last = Some(Delimiter::Stop);
+								break 'item Delimiter::Error(JsonItError::InvalidJsonCharacter {
+									found: char::from(c),
+									location: position.locate(Some(item), scanner.path()),
+								});
+							}
+						}
 					}
-				}
-
-				if c == b'{' {
-					object_nesting += 1;
-					if object_nesting == 1 && array_nesting == 1 {
+					in_string = c == b'"';
+					if in_string && at_item_level {
 						started = true;
-						return Delimiter::Start(StructType::Map);
+						break 'item Delimiter::Start(StructType::String, position, item_index);
 					}
-				}
-
-				if c == b'}' {
-					object_nesting -= 1;
-					if object_nesting == 0 && array_nesting == 1 {
-						started = false;
-						return Delimiter::End(StructType::Map);
+					if at_item_level && matches!(c, b'-' | b'0'..=b'9' | b't' | b'f' | b'n') {
+						started = true;
+						in_scalar = true;
+						break 'item Delimiter::Start(StructType::Scalar(c), position, item_index);
 					}
-				}
 
-				if started {
-					return Delimiter::Item(c);
-				} else {
-					return Delimiter::Skip;
-				}
-			} else {
-				stack_dirty = false;
-			}
+					if c == b'[' {
+						array_nesting += 1;
+						if object_nesting == 0 && array_nesting == 2 {
+							started = true;
+							break 'item Delimiter::Start(StructType::Array, position, item_index);
+						}
+					}
 
-			// if escape char
-			if c == b'\\' {
-				escape = true;
-				return Delimiter::Skip;
-			}
+					if c == b']' {
+						array_nesting -= 1;
 
-			// here we search the key
-			// should never return item, from this point on
-
-			match &state {
-				// handle current key count
-				State::ParseObjectKey => {
-					if c == b'\"' && !escape {
-						state = State::ExpectPoints;
-						// key_stack.push(current_key.);
-						// todo: clean this
-						key_stack.push(current_key.clone());
-						current_key.clear();
-						stack_dirty = true;
-					} else {
-						current_key.push(c);
-					}
-					return Delimiter::Skip;
-				}
-				State::ParseValue(t) => {
-					// detect end of value
-					match t {
-						ParseValueType::String => {
-							if c == b'\"' && !escape {
-								state = State::ParseObject;
-								key_stack.pop();
-								stack_dirty = true;
-							}
-							if escape {
-								escape = false;
-							}
+						if object_nesting == 0 && array_nesting == 1 {
+							started = false;
+							break 'item Delimiter::End(StructType::Array);
 						}
-						ParseValueType::Array => {
-							if c == b']' {
-								state = State::ParseObject;
-								key_stack.pop();
-								stack_dirty = true;
-							}
-						}
-						ParseValueType::Number => {
-							if c == b',' {
-								state = State::ParseObject;
-								key_stack.pop();
-								stack_dirty = true;
+
+						if array_nesting == 0 {
+							in_key = false;
+							scanner.value_consumed();
+							// end of parsing, skip the rest of the stream
+							if !many || scanner.is_done() {
+								break 'item Delimiter::Stop;
 							}
+							break 'item Delimiter::Skip;
 						}
-						ParseValueType::Null => {
-							if c == b',' {
-								state = State::ParseObject;
-								key_stack.pop();
-								stack_dirty = true;
-							}
+					}
+
+					if c == b'{' {
+						object_nesting += 1;
+						if object_nesting == 1 && array_nesting == 1 {
+							started = true;
+							break 'item Delimiter::Start(StructType::Map, position, item_index);
 						}
-						ParseValueType::Map => {
-							// key_stack.push(current_key.clone());
-							// current_key.clear();
-							state = State::ParseObject;
+					}
+
+					if c == b'}' {
+						object_nesting -= 1;
+						if object_nesting == 0 && array_nesting == 1 {
+							started = false;
+							break 'item Delimiter::End(StructType::Map);
 						}
-					};
-				}
-				State::ExpectValue => {
-					if c == b'\"' {
-						state = State::ParseValue(ParseValueType::String);
-					} else if c == b'n' {
-						// speculative nominal value is null
-						state = State::ParseValue(ParseValueType::Null);
-					} else if c == b'{' {
-						state = State::ParseValue(ParseValueType::Map);
-					} else if c == b'[' {
-						state = State::ParseValue(ParseValueType::Array);
-					} else if c == b' ' {
+					}
+
+					if started {
+						Delimiter::Item(c)
 					} else {
-						state = State::ParseValue(ParseValueType::Number);
+						Delimiter::Skip
 					}
-				}
-				State::ExpectPoints => {
-					if c == b':' {
-						state = State::ExpectValue;
+				};
+
+				let delimiter = select(delimiter, &path, many, &mut item_index, &mut selected);
+				return match scalar_end {
+					None => delimiter,
+					// the item ends before the delimiter, or the error, coming with the byte after it
+					Some(end) => {
+						if let Delimiter::Stop | Delimiter::Error(_) = delimiter {
+							last = Some(delimiter);
+						}
+						end
 					}
+				};
+			}
+
+			// here we search the array
+			// should never return item, from this point on
+			let event = match scanner.feed(c, std::slice::from_ref(&path)) {
+				// with wildcards, only the matching arrays are streamed
+				Ok(Event::Found(_)) if c != b'[' && many => scanner.skip_found(c),
+				event => event,
+			};
+			match event {
+				Ok(Event::Found(_)) if c != b'[' => {
+					last = Some(Delimiter::Stop);
+					Delimiter::Error(JsonItError::NotAnArray {
+						found: ValueKind::of(c),
+						location: position.locate(None, scanner.path()),
+					})
 				}
-				State::ParseObject => {
-					if c == b'\"' {
-						state = State::ParseObjectKey;
-					}
-					if c == b'}' {
-						key_stack.pop();
-						stack_dirty = true;
-					}
+				Ok(Event::Found(_)) => {
+					// we are already inside the searched array
+					found = true;
+					in_key = true;
+					array_nesting = 1;
+					item_index = 0;
+					expect = Expect::First;
+					Delimiter::Container(scanner.path())
 				}
-				State::None => {
-					if c == b'{' {
-						// start root of object
-						state = State::ParseObject;
-					} else if c == b'[' {
-						todo!("arrays are unsupported for now");
-					} else if c != b' ' {
-						panic!("malformed");
-					}
+				Ok(Event::Done) if !found => {
+					last = Some(Delimiter::Stop);
+					Delimiter::Error(JsonItError::PathNotFound {
+						path: path.clone(),
+						location: position.locate(None, scanner.path()),
+					})
 				}
-			};
-			Delimiter::Skip
+				Ok(Event::Done) => Delimiter::Stop,
+				Ok(Event::None) => Delimiter::Skip,
+				Err(Unexpected(c)) => {
+					last = Some(Delimiter::Stop);
+					Delimiter::Error(JsonItError::InvalidJsonCharacter {
+						found: char::from(c),
+						location: position.locate(None, scanner.path()),
+					})
+				}
+			}
 		})
-		.take_while(|e| *e != Delimiter::Stop)
+		.take_while(|e| !matches!(e, Delimiter::Stop))
 }
 
-/// Returns an iterator returning serde parsed struct when consumed
+/// Returns an iterator returning serde parsed struct when consumed,
+/// each item being deserialized while its bytes are read
 ///
-///
-///
-///
-pub fn stream_read_items_at<T>(
-	iterator: impl Iterator<Item = u8> + 'static,
-	prefix: &str,
-) -> impl Iterator<Item = serde_json::Result<T>>
+/// The bytes can be given by any iterator, of `u8` or `&u8`, like a `&[u8]` or a `Vec<u8>`, borrowed or not.
+/// The items are the ones of the array at the given [Path], which can also be given as a string to parse.
+/// The document can be encoded in UTF-8, UTF-16 or UTF-32, with or without byte order mark.
+/// An invalid path, a path missing from the document or designating another kind of value,
+/// or a malformed document gives a single error ending the iterator
+pub fn stream_read_items_at<'p, T>(
+	iterator: impl IntoIterator<Item = impl Borrow<u8>>,
+	path: impl IntoPath<'p>,
+) -> impl Iterator<Item = Result<T, JsonItError>>
 where
 	T: DeserializeOwned,
 {
-    let prepared_prefix = make_prefix(prefix);
-	let r1 = iter_delimiters(iterator, prepared_prefix);
+	read_items(iterator.into_iter().map(|c| Ok(*c.borrow())), path)
+}
 
-	fold_and_parse::<T>(r1)
+/// Like [stream_read_items_at], giving each item as raw JSON, validated but not deserialized,
+/// to forward it as is
+pub fn stream_raw_items_at<'p>(
+	iterator: impl IntoIterator<Item = impl Borrow<u8>>,
+	path: impl IntoPath<'p>,
+) -> impl Iterator<Item = Result<Box<RawValue>, JsonItError>> {
+	stream_read_items_at(iterator, path)
+}
+
+/// Like [stream_read_items_at], for bytes which can fail to be read,
+/// like the ones of a [ReaderIter](crate::ReaderIter).
+/// A failed read gives a [JsonItError::IoError] ending the iterator
+pub fn try_stream_read_items_at<'p, T>(
+	iterator: impl IntoIterator<Item = io::Result<u8>>,
+	path: impl IntoPath<'p>,
+) -> impl Iterator<Item = Result<T, JsonItError>>
+where
+	T: DeserializeOwned,
+{
+	read_items(iterator.into_iter(), path)
 }
 
+fn read_items<'p, T>(
+	iterator: impl Iterator<Item = io::Result<u8>>,
+	path: impl IntoPath<'p>,
+) -> impl Iterator<Item = Result<T, JsonItError>>
+where
+	T: DeserializeOwned,
+{
+	let (path, error) = match path.into_path() {
+		Ok(path) => (path.into_owned(), None),
+		Err(e) => (Path::root(), Some(JsonItError::InvalidPath(e))),
+	};
+	// nothing is read when the path is invalid
+	let count = if error.is_some() { 1 } else { usize::MAX };
+	// the document is read as UTF-8, whatever its encoding
+	let r1 = iter_delimiters(Decoder::unbuffered(IterRead::new(iterator)), path);
+
+	let items = Items {
+		delimiters: r1,
+		container: Path::root(),
+		ended: false,
+		output_type: PhantomData,
+	};
+	error.map(Err).into_iter().chain(items).take(count)
+}
@@ -1,7 +1,28 @@
+mod borrowed;
+mod encoding;
 mod iterator;
+mod location;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod multi;
+mod path;
 mod reader;
+mod scanner;
+mod source;
 mod utils;
+mod watched;
 
-pub use iterator::stream_read_items_at;
-pub use reader::{JsonSeqIterator, JsonItError};
+pub use borrowed::JsonSliceIterator;
+pub use iterator::{stream_raw_items_at, stream_read_items_at, try_stream_read_items_at};
+pub use location::Location;
+#[cfg(feature = "mmap")]
+pub use memmap2::Mmap;
+#[cfg(feature = "mmap")]
+pub use mmap::JsonMmapIterator;
+pub use multi::JsonMultiIterator;
+pub use path::{IntoPath, Path, PathError, Segment, Slice};
+pub use reader::{read_value_at, ErrorKind, JsonItError, JsonKvIterator, JsonSeqIterator};
+pub use scanner::ValueKind;
+pub use serde_json::value::RawValue;
+#[allow(deprecated)]
 pub use utils::{make_prefix, ReaderIter};
@@ -0,0 +1,131 @@
+use std::fmt;
+
+use crate::path::Path;
+
+/// Where an error happened in the document
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Location {
+	/// The number of bytes of the document read when the error happened, counted once transcoded to UTF-8
+	pub offset: u64,
+	/// The line of the last byte read, starting at 1
+	pub line: u64,
+	/// The column of the last byte read, starting at 1, counted in bytes
+	pub column: u64,
+	/// The position of the item being read in the streamed array or object, if any
+	pub item: Option<usize>,
+	/// The array or object being streamed, or where the path was being searched
+	pub path: Path,
+}
+
+impl fmt::Display for Location {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"line {}, column {} (byte {}) in {}",
+			self.line, self.column, self.offset, self.path
+		)?;
+		if let Some(item) = self.item {
+			write!(f, ", item {}", item)?;
+		}
+		Ok(())
+	}
+}
+
+/// Counts the bytes read, the lines and the columns
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Position {
+	offset: u64,
+	line: u64,
+	column: u64,
+	after_newline: bool,
+}
+
+impl Default for Position {
+	fn default() -> Self {
+		Self {
+			offset: 0,
+			line: 1,
+			column: 0,
+			after_newline: false,
+		}
+	}
+}
+
+impl Position {
+	pub(crate) fn advance(&mut self, c: u8) {
+		self.offset += 1;
+		if self.after_newline {
+			self.line += 1;
+			self.column = 1;
+		} else {
+			self.column += 1;
+		}
+		self.after_newline = c == b'\n';
+	}
+
+	/// Advances over several bytes at once, as [Position::advance] for each of them
+	pub(crate) fn advance_all(&mut self, bytes: &[u8]) {
+		let Some((last, before)) = bytes.split_last() else {
+			return;
+		};
+		self.offset += bytes.len() as u64;
+		// the lines starting in these bytes, after a newline
+		let newlines = memchr::memchr_iter(b'\n', before).count() as u64 + u64::from(self.after_newline);
+		self.line += newlines;
+		self.column = match memchr::memrchr(b'\n', before) {
+			Some(newline) => (before.len() - newline) as u64,
+			None if self.after_newline => bytes.len() as u64,
+			None => self.column + bytes.len() as u64,
+		};
+		self.after_newline = *last == b'\n';
+	}
+
+	/// The number of bytes read
+	pub(crate) fn offset(&self) -> u64 {
+		self.offset
+	}
+
+	/// The position of a byte after this one, `lines` lines below, at this column when it is on another line
+	pub(crate) fn ahead(&self, bytes: usize, lines: usize, column: usize, after_newline: bool) -> Position {
+		Position {
+			offset: self.offset + bytes as u64,
+			line: self.line + lines as u64,
+			column: match lines {
+				0 => self.column + bytes as u64,
+				_ => column as u64,
+			},
+			after_newline,
+		}
+	}
+
+	/// Where serde stopped on this error, this being the position of the first byte of the value
+	/// and `end` the one of the last byte given to serde, on the same line as the error
+	pub(crate) fn at_error(&self, end: Position, error: &serde_json::Error) -> Position {
+		let (line, column) = (error.line() as u64, error.column() as u64);
+		let line_of_error = self.line + line.saturating_sub(1);
+		let column = match line {
+			0 => return end,
+			1 => self.column.saturating_sub(1) + column,
+			_ => column,
+		};
+		if line_of_error != end.line || column > end.column {
+			return end;
+		}
+		Position {
+			offset: end.offset - (end.column - column),
+			line: line_of_error,
+			column,
+			after_newline: column == end.column && end.after_newline,
+		}
+	}
+
+	pub(crate) fn locate(&self, item: Option<usize>, path: Path) -> Box<Location> {
+		Box::new(Location {
+			offset: self.offset,
+			line: self.line,
+			column: self.column,
+			item,
+			path,
+		})
+	}
+}
@@ -0,0 +1,61 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io;
+
+use memmap2::Mmap;
+use serde::de::DeserializeOwned;
+
+use crate::path::{IntoPath, Path};
+use crate::reader::{ItemReader, JsonItError};
+use crate::scanner::Container;
+use crate::source::InMemory;
+
+/// Holds data in order to parse a memory-mapped file, in UTF-8,
+/// the operating system reading its pages as they are needed.
+/// The items are copied out of the file: to borrow from it,
+/// give the [Mmap] to [JsonSliceIterator::new](crate::JsonSliceIterator::new).
+/// Should be created using [JsonMmapIterator::from_path] or [JsonMmapIterator::from_mmap]
+pub struct JsonMmapIterator<'a, O> {
+	inner: ItemReader<InMemory<Mmap>>,
+	path: Cow<'a, Path>,
+	output_type: std::marker::PhantomData<O>,
+}
+
+impl<'a, O: DeserializeOwned> JsonMmapIterator<'a, O> {
+	/// Maps the file at the given location, then creates a [JsonMmapIterator] streaming the array at the [Path]
+	///
+	/// # Safety
+	///
+	/// The file must not be modified, or truncated, while the iterator is used, see [Mmap::map]
+	pub unsafe fn from_path(
+		file: impl AsRef<std::path::Path>,
+		path_to_look_for: impl IntoPath<'a>,
+	) -> io::Result<Self> {
+		let map = Mmap::map(&File::open(file)?)?;
+		Ok(Self::from_mmap(map, path_to_look_for))
+	}
+
+	/// Creates a new [JsonMmapIterator] from a mapped file and the [Path] of the array to stream,
+	/// which can also be given as a string to parse
+	pub fn from_mmap(map: Mmap, path_to_look_for: impl IntoPath<'a>) -> Self {
+		let (inner, path) = ItemReader::with_path(InMemory::new(map), Container::Array, path_to_look_for);
+		Self {
+			inner,
+			path,
+			output_type: std::marker::PhantomData,
+		}
+	}
+
+	/// Gives no item, instead of [JsonItError::PathNotFound], when the path is missing from the document
+	pub fn missing_as_empty(mut self) -> Self {
+		self.inner.missing_as_empty();
+		self
+	}
+}
+
+impl<O: DeserializeOwned> Iterator for JsonMmapIterator<'_, O> {
+	type Item = Result<O, JsonItError>;
+	fn next(&mut self) -> Option<Self::Item> {
+		self.inner.next_at(&self.path)
+	}
+}
@@ -0,0 +1,97 @@
+use std::borrow::Cow;
+use std::io::Read;
+
+use serde::de::DeserializeOwned;
+
+use crate::path::{IntoPath, Path};
+use crate::reader::{ItemReader, JsonItError, State};
+use crate::scanner::Container;
+use crate::source::Buffered;
+
+type Route<'a, R, E> = Box<dyn Fn(&mut ItemReader<Buffered<R>>, u8) -> Result<E, JsonItError> + 'a>;
+
+/// Streams the items of several arrays in a single read of a [Read], in document order.
+/// Each [Path] is registered with its own item type and a function wrapping the items into a common type `E`,
+/// usually a variant of an enum telling where the item comes from.
+/// Only arrays can be registered: a single value is read with [read_value_at](crate::read_value_at).
+///
+/// Should be created using [JsonMultiIterator::new], then [JsonMultiIterator::with] for each path
+///
+/// ```
+/// # use serde::Deserialize;
+/// # use jsonit::JsonMultiIterator;
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// enum Record {
+///     User(User),
+///     Order(u32),
+/// }
+///
+/// let data = r#"{"users": [{"name": "a"}], "orders": [1, 2], "meta": {}}"#;
+/// let iterator = JsonMultiIterator::new(data.as_bytes())
+///     .with("users", Record::User)
+///     .with("orders", Record::Order);
+/// for record in iterator {
+///     match record.unwrap() {
+///         Record::User(user) => println!("user {}", user.name),
+///         Record::Order(order) => println!("order {}", order),
+///     }
+/// }
+/// ```
+pub struct JsonMultiIterator<'a, R, E> {
+	inner: ItemReader<Buffered<R>>,
+	paths: Vec<Cow<'a, Path>>,
+	routes: Vec<Route<'a, R, E>>,
+}
+
+impl<'a, R: Read, E> JsonMultiIterator<'a, R, E> {
+	/// Creates a new [JsonMultiIterator] from a [Read], without any path yet
+	pub fn new(reader: R) -> Self {
+		Self {
+			inner: ItemReader::new(reader, Container::Array),
+			paths: vec![],
+			routes: vec![],
+		}
+	}
+
+	/// Registers the [Path] of an array, whose items are deserialized as `T` then given to `wrap`.
+	/// When an array is designated by several paths, the first one registered is used.
+	/// A value found at the path which is not an array gives [JsonItError::NotAnArray].
+	/// As the items are deserialized whole, a path inside the items of another registered array is never found,
+	/// giving [JsonItError::PathNotFound] unless [JsonMultiIterator::missing_as_empty] is used.
+	/// An invalid path is returned as the first error of the iterator
+	pub fn with<T: DeserializeOwned>(mut self, path: impl IntoPath<'a>, wrap: impl Fn(T) -> E + 'a) -> Self {
+		match path.into_path() {
+			Ok(path) => {
+				self.paths.push(path);
+				self.routes
+					.push(Box::new(move |inner, first| inner.deserialize_item(first).map(&wrap)));
+			}
+			Err(e) => {
+				if !matches!(self.inner.state, State::InvalidPath(_)) {
+					self.inner.state = State::InvalidPath(e);
+				}
+			}
+		}
+		self
+	}
+
+	/// Gives no item for the paths missing from the document, instead of [JsonItError::PathNotFound]
+	pub fn missing_as_empty(mut self) -> Self {
+		self.inner.missing_as_empty();
+		self
+	}
+}
+
+impl<R: Read, E> Iterator for JsonMultiIterator<'_, R, E> {
+	type Item = Result<E, JsonItError>;
+	fn next(&mut self) -> Option<Self::Item> {
+		match self.inner.next_item(&self.paths)? {
+			Ok((route, first)) => Some(self.routes[route](&mut self.inner, first)),
+			Err(e) => Some(Err(e)),
+		}
+	}
+}
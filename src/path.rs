@@ -0,0 +1,617 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::{Range, RangeFrom};
+use std::str::FromStr;
+
+/// A segment of a [Path], one level of nesting in the document
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+pub enum Segment {
+	/// The member of an object with this key
+	Key(String),
+	/// The element of an array at this position,
+	/// or the member of an object whose key is this position written in decimal, as in JSON Pointer
+	Index(usize),
+	/// Any member of an object or element of an array
+	Wildcard,
+	/// Any number of levels, including none, as the recursive descent `..` of JSONPath
+	Descendants,
+	/// The elements of an array whose position is in the slice
+	Slice(Slice),
+}
+
+/// Positions in an array, from `start` included to `end` excluded, every `step` elements,
+/// as the `[start:end:step]` selector of JSONPath.
+/// Negative positions are not supported, as the length of an array is not known while streaming it
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct Slice {
+	pub start: usize,
+	/// [None] goes up to the end of the array
+	pub end: Option<usize>,
+	pub step: usize,
+}
+
+impl Slice {
+	/// Keeps every `step` element instead of all of them
+	pub fn with_step(self, step: usize) -> Self {
+		Self { step, ..self }
+	}
+
+	pub fn contains(&self, index: usize) -> bool {
+		index >= self.start
+			&& self.end.map_or(true, |end| index < end)
+			&& (index - self.start) % self.step.max(1) == 0
+	}
+
+	/// Tells if no position after this one is in the slice
+	pub(crate) fn is_past(&self, index: usize) -> bool {
+		self.end.is_some_and(|end| index >= end)
+	}
+}
+
+impl From<Range<usize>> for Slice {
+	fn from(range: Range<usize>) -> Self {
+		Self {
+			start: range.start,
+			end: Some(range.end),
+			step: 1,
+		}
+	}
+}
+
+impl From<RangeFrom<usize>> for Slice {
+	fn from(range: RangeFrom<usize>) -> Self {
+		Self {
+			start: range.start,
+			end: None,
+			step: 1,
+		}
+	}
+}
+
+impl fmt::Display for Slice {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}:", self.start)?;
+		if let Some(end) = self.end {
+			write!(f, "{}", end)?;
+		}
+		if self.step != 1 {
+			write!(f, ":{}", self.step)?;
+		}
+		Ok(())
+	}
+}
+
+/// Designates the array to stream in a document
+///
+/// It can be parsed from a string, where segments are separated by dots, like `pages.0.items`:
+/// - a segment made of digits, without leading zero, is an array index, see [Segment::Index]
+/// - `*` matches every member of an object or element of an array
+/// - any other segment is an object key, a key can be written as a quoted JSON string
+///   when it contains dots or would be read otherwise, like `by_id."123".values`, and must be when it is empty
+/// - an empty string or `$` is the root of the document
+///
+/// A string starting with `/` is parsed as a JSON Pointer (RFC 6901), like `/pages/0/items`.
+///
+/// A string starting with `$` is parsed as a JSONPath, see [Path::from_jsonpath] for the supported subset.
+///
+/// or built with [Path::root], like `Path::root().key("pages").index(0).key("items")`.
+/// Its [Display](fmt::Display) gives back a string parsing to the same path.
+#[derive(PartialEq, Eq, Debug, Clone, Hash, Default)]
+pub struct Path {
+	segments: Vec<Segment>,
+	/// Only the items of the array at these positions are returned, all of them when [None]
+	items: Option<Slice>,
+}
+
+impl Path {
+	/// The root of the document, to stream the items of a top-level array
+	pub fn root() -> Self {
+		Self::default()
+	}
+
+	/// Parses a path, see [Path] for the syntax
+	pub fn parse(path: &str) -> Result<Self, PathError> {
+		path.parse()
+	}
+
+	/// Parses a JSON Pointer (RFC 6901), like `/data/items`, where `~1` stands for `/` and `~0` for `~`
+	pub fn from_pointer(pointer: &str) -> Result<Self, PathError> {
+		let error = |position, reason| PathError {
+			path: pointer.to_owned(),
+			position,
+			reason,
+		};
+		if pointer.is_empty() {
+			return Ok(Self::root());
+		}
+		if !pointer.starts_with('/') {
+			return Err(error(0, "a JSON Pointer must start with '/'"));
+		}
+		let mut segments = vec![];
+		let mut position = 1;
+		for token in pointer[1..].split('/') {
+			let segment = if is_index(token) {
+				Segment::Index(token.parse().map_err(|_| error(position, "index is too large"))?)
+			} else {
+				let mut key = String::with_capacity(token.len());
+				let mut chars = token.char_indices();
+				while let Some((i, c)) = chars.next() {
+					match c {
+						'~' => match chars.next() {
+							Some((_, '0')) => key.push('~'),
+							Some((_, '1')) => key.push('/'),
+							_ => return Err(error(position + i, "'~' must be followed by '0' or '1'")),
+						},
+						c => key.push(c),
+					}
+				}
+				Segment::Key(key)
+			};
+			segments.push(segment);
+			position += token.len() + 1;
+		}
+		Ok(Self { segments, items: None })
+	}
+
+	/// Parses a JSONPath, limited to what can be evaluated while streaming:
+	/// - `.key`, `['key']` or `["key"]` for object keys
+	/// - `[2]` for array indices, `.*` or `[*]` for wildcards
+	/// - `..key`, `..*` or `..[2]` for the recursive descent, matching at any depth
+	/// - `[start:end:step]` for slices, without negative positions
+	///
+	/// The JSONPath selects the items to return: a trailing `[*]`, index or slice applies to the items of the array,
+	/// so `$.a.b[*]` is the same as `a.b`, and `$.a[2:10]` streams the items of `a` from position 2 to 9.
+	/// Without such a trailing selector, every item of the designated arrays is returned.
+	/// When a match is found inside an item already returned, it is not returned again.
+	pub fn from_jsonpath(jsonpath: &str) -> Result<Self, PathError> {
+		let error = |position, reason| PathError {
+			path: jsonpath.to_owned(),
+			position,
+			reason,
+		};
+		let bytes = jsonpath.as_bytes();
+		if bytes.first() != Some(&b'$') {
+			return Err(error(0, "a JSONPath must start with '$'"));
+		}
+		let mut segments = vec![];
+		let mut position = 1;
+		// tells if the last segment was given as an array selector, it then applies to the items
+		let mut last_selects_items = false;
+		while position < bytes.len() {
+			let descendants = jsonpath[position..].starts_with("..");
+			if descendants {
+				segments.push(Segment::Descendants);
+				position += 2;
+			} else if bytes[position] == b'.' {
+				position += 1;
+			} else if bytes[position] != b'[' {
+				return Err(error(position, "expected '.' or '['"));
+			}
+			match bytes.get(position) {
+				Some(b'[') => {
+					let len = jsonpath[position..]
+						.find(']')
+						.filter(|_| !matches!(bytes.get(position + 1), Some(b'\'' | b'"')))
+						.map(|e| e + 1);
+					let (segment, len) = match len {
+						Some(len) => (parse_selector(&jsonpath[position + 1..position + len - 1]), len),
+						None => {
+							let quote = *bytes
+								.get(position + 1)
+								.ok_or_else(|| error(position, "unterminated '['"))?;
+							let len = quoted_len(&jsonpath[position + 1..], quote)
+								.ok_or_else(|| error(position, "unterminated quoted key"))?;
+							let key = unescape(&jsonpath[position + 2..position + len]);
+							if bytes.get(position + 1 + len) != Some(&b']') {
+								return Err(error(position + 1 + len, "expected ']' after a quoted key"));
+							}
+							(Ok(Segment::Key(key)), len + 2)
+						}
+					};
+					let segment = segment.map_err(|reason| error(position, reason))?;
+					last_selects_items = !matches!(segment, Segment::Key(_));
+					segments.push(segment);
+					position += len;
+				}
+				Some(_) => {
+					let len = jsonpath[position..]
+						.find(['.', '['])
+						.unwrap_or(jsonpath.len() - position);
+					let name = &jsonpath[position..position + len];
+					if name.is_empty() {
+						return Err(error(position, "expected a key"));
+					}
+					if name == "*" {
+						segments.push(Segment::Wildcard);
+						last_selects_items = true;
+					} else {
+						segments.push(Segment::Key(name.to_owned()));
+						last_selects_items = false;
+					}
+					position += len;
+				}
+				None => return Err(error(position, "expected a key")),
+			}
+		}
+		let mut path = Self { segments, items: None };
+		if last_selects_items {
+			match path.segments.pop() {
+				Some(Segment::Index(index)) => path.items = Some(Slice::from(index..index + 1)),
+				Some(Segment::Slice(slice)) => path.items = Some(slice),
+				_ => {}
+			}
+		}
+		Ok(path)
+	}
+
+	/// Writes this path as a JSON Pointer (RFC 6901),
+	/// gives [None] when it contains a segment which can not be expressed as one, like a wildcard
+	pub fn to_pointer(&self) -> Option<String> {
+		if self.needs_jsonpath() {
+			return None;
+		}
+		let mut pointer = String::new();
+		for segment in &self.segments {
+			pointer.push('/');
+			match segment {
+				Segment::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+				Segment::Index(index) => pointer.push_str(&index.to_string()),
+				Segment::Wildcard | Segment::Descendants | Segment::Slice(_) => return None,
+			}
+		}
+		Some(pointer)
+	}
+
+	/// Adds a segment matching the member of an object with this key
+	pub fn key(mut self, key: impl Into<String>) -> Self {
+		self.segments.push(Segment::Key(key.into()));
+		self
+	}
+
+	/// Adds a segment matching the element of an array at this position
+	pub fn index(mut self, index: usize) -> Self {
+		self.segments.push(Segment::Index(index));
+		self
+	}
+
+	/// Adds a segment matching any member of an object or element of an array
+	pub fn wildcard(mut self) -> Self {
+		self.segments.push(Segment::Wildcard);
+		self
+	}
+
+	/// Adds a segment matching any number of levels, as the recursive descent `..` of JSONPath
+	pub fn descendants(mut self) -> Self {
+		self.segments.push(Segment::Descendants);
+		self
+	}
+
+	/// Adds a segment matching the elements of an array whose position is in the slice
+	pub fn slice(mut self, slice: impl Into<Slice>) -> Self {
+		self.segments.push(Segment::Slice(slice.into()));
+		self
+	}
+
+	/// Only returns the items of the array whose position is in the slice
+	pub fn with_items(mut self, slice: impl Into<Slice>) -> Self {
+		self.items = Some(slice.into());
+		self
+	}
+
+	pub fn segments(&self) -> &[Segment] {
+		&self.segments
+	}
+
+	/// The positions of the items to return, all of them when [None]
+	pub fn items(&self) -> Option<&Slice> {
+		self.items.as_ref()
+	}
+
+	pub fn is_root(&self) -> bool {
+		self.segments.is_empty()
+	}
+
+	/// Tells if this path can designate more than one array
+	pub(crate) fn matches_many(&self) -> bool {
+		self.segments.iter().any(|s| match s {
+			Segment::Key(_) | Segment::Index(_) => false,
+			Segment::Slice(slice) => slice.end != Some(slice.start + 1),
+			Segment::Wildcard | Segment::Descendants => true,
+		})
+	}
+
+	/// The path of the values themselves, rather than of the array containing them:
+	/// the positions of the items to return become the last segment
+	pub(crate) fn to_value_path(&self) -> Cow<'_, Path> {
+		match self.items {
+			None => Cow::Borrowed(self),
+			Some(items) => Cow::Owned(self.clone().slice(items)),
+		}
+	}
+
+	/// Tells if the item at this position in the array has to be returned
+	pub(crate) fn selects_item(&self, index: usize) -> bool {
+		self.items.map_or(true, |items| items.contains(index))
+	}
+
+	/// Tells if no item after this position in the array has to be returned
+	pub(crate) fn is_past_items(&self, index: usize) -> bool {
+		self.items.is_some_and(|items| items.is_past(index))
+	}
+
+	/// Can not be written with dots or as a JSON Pointer
+	fn needs_jsonpath(&self) -> bool {
+		self.items.is_some()
+			|| self
+				.segments
+				.iter()
+				.any(|s| matches!(s, Segment::Descendants | Segment::Slice(_)))
+	}
+}
+
+impl FromStr for Path {
+	type Err = PathError;
+
+	fn from_str(path: &str) -> Result<Self, Self::Err> {
+		if path.is_empty() || path == "$" {
+			return Ok(Self::root());
+		}
+		if path.starts_with('/') {
+			return Self::from_pointer(path);
+		}
+		if path.starts_with('$') {
+			return Self::from_jsonpath(path);
+		}
+		let error = |position, reason| PathError {
+			path: path.to_owned(),
+			position,
+			reason,
+		};
+		let bytes = path.as_bytes();
+		let mut segments = vec![];
+		let mut position = 0;
+		loop {
+			let rest = &path[position..];
+			let (segment, len) = if rest.starts_with('"') {
+				let len = quoted_len(rest, b'"').ok_or_else(|| error(position, "unterminated quoted key"))?;
+				let key =
+					serde_json::from_str::<String>(&rest[..len]).map_err(|_| error(position, "invalid quoted key"))?;
+				(Segment::Key(key), len)
+			} else {
+				let len = rest.find('.').unwrap_or(rest.len());
+				let raw = &rest[..len];
+				let segment = if raw.is_empty() {
+					return Err(error(position, "empty key, which must be quoted"));
+				} else if raw == "*" {
+					Segment::Wildcard
+				} else if is_index(raw) {
+					Segment::Index(raw.parse().map_err(|_| error(position, "index is too large"))?)
+				} else {
+					Segment::Key(raw.to_owned())
+				};
+				(segment, len)
+			};
+			segments.push(segment);
+			position += len;
+			match bytes.get(position) {
+				None => break,
+				Some(b'.') => position += 1,
+				Some(_) => return Err(error(position, "expected '.' after a quoted key")),
+			}
+		}
+		Ok(Self { segments, items: None })
+	}
+}
+
+/// Parses what is between brackets in a JSONPath, except quoted keys
+fn parse_selector(selector: &str) -> Result<Segment, &'static str> {
+	let selector = selector.trim();
+	if selector == "*" {
+		return Ok(Segment::Wildcard);
+	}
+	if selector.starts_with('-') || selector.contains(":-") {
+		return Err("negative positions are not supported while streaming");
+	}
+	let number = |e: &str| e.trim().parse::<usize>().map_err(|_| "invalid array selector");
+	let parts = selector.split(':').collect::<Vec<_>>();
+	match parts[..] {
+		[index] => Ok(Segment::Index(number(index)?)),
+		[start, end] | [start, end, ""] => Ok(Segment::Slice(Slice {
+			start: if start.trim().is_empty() { 0 } else { number(start)? },
+			end: if end.trim().is_empty() {
+				None
+			} else {
+				Some(number(end)?)
+			},
+			step: 1,
+		})),
+		[start, end, step] => {
+			let step = number(step)?;
+			if step == 0 {
+				return Err("the step of a slice can not be 0");
+			}
+			Ok(Segment::Slice(Slice {
+				start: if start.trim().is_empty() { 0 } else { number(start)? },
+				end: if end.trim().is_empty() {
+					None
+				} else {
+					Some(number(end)?)
+				},
+				step,
+			}))
+		}
+		_ => Err("invalid array selector"),
+	}
+}
+
+/// Removes the backslashes escaping characters in a quoted JSONPath key
+fn unescape(key: &str) -> String {
+	let mut unescaped = String::with_capacity(key.len());
+	let mut escape = false;
+	for c in key.chars() {
+		if c == '\\' && !escape {
+			escape = true;
+		} else {
+			escape = false;
+			unescaped.push(c);
+		}
+	}
+	unescaped
+}
+
+/// Tells if a segment is an array index: digits without leading zero
+fn is_index(segment: &str) -> bool {
+	!segment.is_empty() && segment.bytes().all(|c| c.is_ascii_digit()) && (segment == "0" || !segment.starts_with('0'))
+}
+
+/// Length of the string quoted with `quote` at the start of `s`, quotes included
+fn quoted_len(s: &str, quote: u8) -> Option<usize> {
+	let mut escape = false;
+	for (i, c) in s.bytes().enumerate().skip(1) {
+		if c == quote && !escape {
+			return Some(i + 1);
+		}
+		escape = !escape && c == b'\\';
+	}
+	None
+}
+
+/// Tells if a key has to be quoted in order to be parsed back as the same key
+fn needs_quotes(key: &str) -> bool {
+	key.is_empty()
+		|| key == "*"
+		|| key.bytes().all(|c| c.is_ascii_digit())
+		|| key.contains(['.', '"'])
+		|| key.starts_with(['$', '/'])
+}
+
+impl Path {
+	fn fmt_jsonpath(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("$")?;
+		let mut after_descendants = false;
+		for segment in &self.segments {
+			match segment {
+				Segment::Key(key) if is_identifier(key) => {
+					if !after_descendants {
+						f.write_str(".")?;
+					}
+					f.write_str(key)?;
+				}
+				Segment::Key(key) => write!(f, "['{}']", key.replace('\\', "\\\\").replace('\'', "\\'"))?,
+				Segment::Index(index) => write!(f, "[{}]", index)?,
+				Segment::Wildcard => f.write_str("[*]")?,
+				Segment::Descendants => f.write_str("..")?,
+				Segment::Slice(slice) => write!(f, "[{}]", slice)?,
+			}
+			after_descendants = *segment == Segment::Descendants;
+		}
+		match self.items {
+			None => f.write_str("[*]"),
+			Some(items) if items.step == 1 && items.end == Some(items.start + 1) => write!(f, "[{}]", items.start),
+			Some(items) => write!(f, "[{}]", items),
+		}
+	}
+}
+
+/// Tells if a key can be written after a dot in a JSONPath
+fn is_identifier(key: &str) -> bool {
+	!key.is_empty() && key.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'-')
+}
+
+impl fmt::Display for Path {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.is_root() && self.items.is_none() {
+			return f.write_str("$");
+		}
+		if self.needs_jsonpath() {
+			return self.fmt_jsonpath(f);
+		}
+		for (i, segment) in self.segments.iter().enumerate() {
+			if i > 0 {
+				f.write_str(".")?;
+			}
+			match segment {
+				Segment::Key(key) if needs_quotes(key) => {
+					f.write_str(&serde_json::to_string(key).map_err(|_| fmt::Error)?)?
+				}
+				Segment::Key(key) => f.write_str(key)?,
+				Segment::Index(index) => write!(f, "{}", index)?,
+				Segment::Wildcard => f.write_str("*")?,
+				Segment::Descendants | Segment::Slice(_) => unreachable!("written as a JSONPath"),
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Error returned when a [Path] can not be parsed
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PathError {
+	/// The path which failed to parse
+	pub path: String,
+	/// The position of the error in the path, in bytes
+	pub position: usize,
+	pub reason: &'static str,
+}
+
+impl fmt::Display for PathError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "invalid path {:?} at {}: {}", self.path, self.position, self.reason)
+	}
+}
+
+impl std::error::Error for PathError {}
+
+/// Anything which can be used as a [Path]: a [Path], a reference to one,
+/// or a string to parse as a dotted path, a JSON Pointer or a JSONPath
+pub trait IntoPath<'a> {
+	fn into_path(self) -> Result<Cow<'a, Path>, PathError>;
+}
+
+impl<'a> IntoPath<'a> for &'a Path {
+	fn into_path(self) -> Result<Cow<'a, Path>, PathError> {
+		Ok(Cow::Borrowed(self))
+	}
+}
+
+impl IntoPath<'_> for Path {
+	fn into_path(self) -> Result<Cow<'static, Path>, PathError> {
+		Ok(Cow::Owned(self))
+	}
+}
+
+impl IntoPath<'_> for &str {
+	fn into_path(self) -> Result<Cow<'static, Path>, PathError> {
+		self.parse().map(Cow::Owned)
+	}
+}
+
+impl IntoPath<'_> for &String {
+	fn into_path(self) -> Result<Cow<'static, Path>, PathError> {
+		self.as_str().into_path()
+	}
+}
+
+impl IntoPath<'_> for String {
+	fn into_path(self) -> Result<Cow<'static, Path>, PathError> {
+		self.as_str().into_path()
+	}
+}
+
+impl IntoPath<'_> for &[u8] {
+	fn into_path(self) -> Result<Cow<'static, Path>, PathError> {
+		std::str::from_utf8(self)
+			.map_err(|e| PathError {
+				path: String::from_utf8_lossy(self).into_owned(),
+				position: e.valid_up_to(),
+				reason: "path is not valid UTF-8",
+			})?
+			.into_path()
+	}
+}
+
+impl<const N: usize> IntoPath<'_> for &[u8; N] {
+	fn into_path(self) -> Result<Cow<'static, Path>, PathError> {
+		self[..].into_path()
+	}
+}
@@ -1,264 +1,704 @@
-use std::char;
+use std::borrow::{Borrow, Cow};
+use std::fmt;
 
-use crate::utils::compare_stack_reader;
+use crate::encoding::Decoder;
+use crate::location::{Location, Position};
+use crate::path::{IntoPath, Path, PathError};
+use crate::scanner::{is_scalar_byte, is_whitespace, Container, Event, Scanner, Unexpected, ValueKind};
+use crate::source::{deserialize_token, Buffered, Counted, Source};
 
-use {serde::de::DeserializeOwned, std::io::Read};
+use {
+	serde::de::{Deserialize, DeserializeOwned, IgnoredAny},
+	serde_json::value::RawValue,
+	std::io::Read,
+};
 
 /// Holds data in order to parse a stream of u8, represented as a [Read]
+/// in UTF-8, UTF-16 or UTF-32, with or without byte order mark.
 /// Should be created using [JsonSeqIterator::new]
 pub struct JsonSeqIterator<'a, R, O> {
-	state: State<'a>,
-	reader: R,
+	inner: ItemReader<Buffered<R>>,
+	path: Cow<'a, Path>,
 	output_type: std::marker::PhantomData<O>,
 }
 
-enum State<'a> {
-	NotStarted { path_to_look_for: PrefixPath<'a> },
-	Started,
-	Ended,
+impl<'a, R: Read, O: DeserializeOwned> JsonSeqIterator<'a, R, O> {
+	/// Creates a new [JsonSeqIterator] from a [Read] and the [Path] of the array to stream,
+	/// which can also be given as a string to parse
+	pub fn new(reader: R, path_to_look_for: impl IntoPath<'a>) -> Self {
+		let (inner, path) =
+			ItemReader::with_path(Counted::new(Decoder::new(reader)), Container::Array, path_to_look_for);
+		Self {
+			inner,
+			path,
+			output_type: std::marker::PhantomData,
+		}
+	}
+
+	/// Gives no item, instead of [JsonItError::PathNotFound], when the path is missing from the document
+	pub fn missing_as_empty(mut self) -> Self {
+		self.inner.missing_as_empty();
+		self
+	}
+
+	/// Creates a [JsonKvIterator], giving the members of the object at the [Path] instead of the items of an array,
+	/// like the kvitems function of ijson
+	pub fn kvitems(reader: R, path_to_look_for: impl IntoPath<'a>) -> JsonKvIterator<'a, R, O> {
+		JsonKvIterator::new(reader, path_to_look_for)
+	}
 }
 
-type PrefixPath<'a> = &'a [u8];
+impl<'a, R: Read> JsonSeqIterator<'a, R, Box<RawValue>> {
+	/// Creates a [JsonSeqIterator] giving each item as raw JSON, validated but not deserialized,
+	/// to forward it as is
+	pub fn raw(reader: R, path_to_look_for: impl IntoPath<'a>) -> Self {
+		Self::new(reader, path_to_look_for)
+	}
+}
 
-impl<'a, R: Read, O: DeserializeOwned> JsonSeqIterator<'a, R, O> {
-	
-	/// Creates a new [JsonSeqIterator] from a [Read] and a given prefix
-	pub fn new(reader: R, path_to_look_for: &'a [u8]) -> Self {
+/// Holds data in order to parse the members of an object in a stream of u8, represented as a [Read],
+/// one at a time, as `(key, value)` pairs.
+/// Should be created using [JsonKvIterator::new] or [JsonSeqIterator::kvitems]
+pub struct JsonKvIterator<'a, R, O> {
+	inner: ItemReader<Buffered<R>>,
+	path: Cow<'a, Path>,
+	output_type: std::marker::PhantomData<O>,
+}
+
+impl<'a, R: Read, O: DeserializeOwned> JsonKvIterator<'a, R, O> {
+	/// Creates a new [JsonKvIterator] from a [Read] and the [Path] of the object to stream,
+	/// which can also be given as a string to parse.
+	/// With wildcards, the members of every matching object are returned one after the other
+	pub fn new(reader: R, path_to_look_for: impl IntoPath<'a>) -> Self {
+		let (inner, path) =
+			ItemReader::with_path(Counted::new(Decoder::new(reader)), Container::Object, path_to_look_for);
 		Self {
-			state: State::NotStarted { path_to_look_for },
-			reader,
+			inner,
+			path,
 			output_type: std::marker::PhantomData,
 		}
 	}
 
-	fn next_char(&mut self) -> Result<u8, JsonItError> {
-		let mut buf = [0_u8; 1];
-		self.reader.read_exact(&mut buf).map_err(JsonItError::IoError)?;
-		Ok(buf[0])
-	}
-
-	fn deserialize_one_item(&mut self, maybe_byte: Option<u8>) -> Result<O, JsonItError> {
-		match maybe_byte {
-			Some(w) => {
-				let r = &[w][..];
-				O::deserialize(&mut serde_json::Deserializer::from_reader(
-					&mut r.chain(self.reader.by_ref()),
-				))
-			}
-			None => O::deserialize(&mut serde_json::Deserializer::from_reader(&mut self.reader)),
-		}
-		.map_err(JsonItError::SerdeError)
+	/// Gives no member, instead of [JsonItError::PathNotFound], when the path is missing from the document
+	pub fn missing_as_empty(mut self) -> Self {
+		self.inner.missing_as_empty();
+		self
 	}
 }
 
-#[derive(PartialEq, Debug, Copy, Clone)]
-enum ParseValueType {
-	String,
-	Number,
-	Null,
-	Map,
-	Array,
+impl<R: Read, O: DeserializeOwned> Iterator for JsonKvIterator<'_, R, O> {
+	type Item = Result<(String, O), JsonItError>;
+	fn next(&mut self) -> Option<Self::Item> {
+		let value = self.inner.next_at(&self.path)?;
+		Some(value.map(|value| (std::mem::take(&mut self.inner.key), value)))
+	}
 }
 
-#[derive(PartialEq, Debug)]
-enum NotStartedState {
-	ParseObjectKey,
-	ParseObject,
-	ParseValue(ParseValueType),
-	ExpectValue,
-	/// We expect ":" or whitespace
-	ExpectPoints,
-	None,
-}
- 
 impl<R: Read, O: DeserializeOwned> Iterator for JsonSeqIterator<'_, R, O> {
 	type Item = Result<O, JsonItError>;
 	fn next(&mut self) -> Option<Self::Item> {
-		match self.state {
-			State::NotStarted { path_to_look_for } => {
-				// TODO advance the reader to the path. As a stub:
-				let mut key_stack: Vec<Vec<u8>> = vec![];
-				// the current key where we parse the value
-				let mut current_key: Vec<u8> = vec![];
-				// keeps state of the parsing
-				let mut state = NotStartedState::None;
-				// prevents rebuilding the key stack without rebuilding it
-				let mut stack_dirty = false;
-				// Keeps state if the next character is escaped
-				let mut escape = false;
-				loop {
-					match self.next_char() {
+		self.inner.next_at(&self.path)
+	}
+}
+
+/// Reads the single value at the [Path] in a [Read], which can also be given as a string to parse,
+/// then stops reading.
+/// A trailing index of a JSONPath designates an element of the array, like `$.pages[0]`.
+/// Gives [JsonItError::PathNotFound] if the document ends before the path is found
+pub fn read_value_at<'a, T: DeserializeOwned>(reader: impl Read, path: impl IntoPath<'a>) -> Result<T, JsonItError> {
+	let path = path.into_path().map_err(JsonItError::InvalidPath)?;
+	let mut inner = ItemReader::new(reader, Container::Array);
+	match inner.find_value(&[path.to_value_path()])? {
+		Some((_, first)) => inner.deserialize_item(first),
+		None => Err(JsonItError::PathNotFound {
+			path: path.into_owned(),
+			location: inner.location(),
+		}),
+	}
+}
+
+pub(crate) enum State {
+	/// The path could not be parsed, the error is returned by the first call to [Iterator::next]
+	InvalidPath(PathError),
+	NotStarted,
+	Started,
+	Ended,
+}
+
+/// What can come next in the array, or object, being streamed
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub(crate) enum Expect {
+	/// An item or the end, right after the opening bracket
+	First,
+	/// An item, after a comma
+	Next,
+	/// A comma or the end, after an item
+	After,
+}
+
+/// Finds the items of the arrays, or the members of the objects, designated by one or more paths in a [Source],
+/// leaving their deserialization to the iterator using it
+pub(crate) struct ItemReader<S> {
+	pub(crate) state: State,
+	container: Container,
+	/// The key of the current member, when streaming an object
+	pub(crate) key: String,
+	/// keeps track of where we are in the document
+	scanner: Scanner,
+	/// Offsets in errors count the bytes of the document transcoded to UTF-8
+	reader: S,
+	/// The path designating the current array, and the position of the next item in it
+	current: usize,
+	item_index: usize,
+	/// The position of the item being read, for errors
+	item: Option<usize>,
+	/// What can come next in the current array
+	expect: Expect,
+	/// The paths which designated an array already
+	found: Vec<bool>,
+	/// If a path missing from the document gives no item instead of [JsonItError::PathNotFound]
+	missing_as_empty: bool,
+}
+
+impl<R: Read> ItemReader<Buffered<R>> {
+	pub(crate) fn new(reader: R, container: Container) -> Self {
+		Self::with_source(Counted::new(Decoder::new(reader)), container)
+	}
+}
+
+impl<'de, S: Source<'de>> ItemReader<S> {
+	pub(crate) fn with_source(reader: S, container: Container) -> Self {
+		Self {
+			state: State::NotStarted,
+			container,
+			key: String::new(),
+			scanner: Scanner::new(),
+			reader,
+			current: 0,
+			item_index: 0,
+			item: None,
+			expect: Expect::First,
+			found: vec![],
+			missing_as_empty: false,
+		}
+	}
+
+	/// Creates an [ItemReader] looking for the single path of an iterator, which can also be given as a string to parse.
+	/// An invalid path is replaced by the root, its error being given by the first call to [Iterator::next]
+	pub(crate) fn with_path<'a>(reader: S, container: Container, path: impl IntoPath<'a>) -> (Self, Cow<'a, Path>) {
+		let mut inner = Self::with_source(reader, container);
+		let path = match path.into_path() {
+			Ok(path) => path,
+			Err(e) => {
+				inner.state = State::InvalidPath(e);
+				Cow::Owned(Path::root())
+			}
+		};
+		(inner, path)
+	}
+
+	/// Gives no item, instead of [JsonItError::PathNotFound], when the paths are missing from the document
+	pub(crate) fn missing_as_empty(&mut self) {
+		self.missing_as_empty = true;
+	}
+
+	/// Where we are in the document, for errors
+	pub(crate) fn location(&self) -> Box<Location> {
+		self.reader.position().locate(self.item, self.scanner.path())
+	}
+
+	fn serde_error(&self, error: serde_json::Error) -> JsonItError {
+		JsonItError::SerdeError {
+			error,
+			location: self.location(),
+		}
+	}
+
+	/// An error of serde at the given position, in the item being read
+	fn serde_error_at(&self, position: Position, error: serde_json::Error) -> JsonItError {
+		JsonItError::SerdeError {
+			error,
+			location: position.locate(self.item, self.scanner.path()),
+		}
+	}
+
+	fn unexpected(&self, Unexpected(c): Unexpected) -> JsonItError {
+		JsonItError::InvalidJsonCharacter {
+			found: char::from(c),
+			location: self.location(),
+		}
+	}
+
+	fn next_char(&mut self) -> Result<u8, JsonItError> {
+		match self.reader.next_byte() {
+			Ok(Some(c)) => Ok(c),
+			Ok(None) => Err(JsonItError::IoError {
+				error: std::io::ErrorKind::UnexpectedEof.into(),
+				location: self.location(),
+			}),
+			Err(error) => Err(JsonItError::IoError {
+				error,
+				location: self.location(),
+			}),
+		}
+	}
+
+	/// Deserializes the item starting with the given byte, as given by [ItemReader::next_item]
+	pub(crate) fn deserialize_item<T: Deserialize<'de>>(&mut self, first: u8) -> Result<T, JsonItError> {
+		let in_source = S::SCALARS || matches!(first, b'{' | b'[' | b'"');
+		let item = if in_source {
+			self.reader
+				.deserialize(first)
+				.map_err(|e| self.serde_error_at(self.reader.error_position(), e))
+		} else {
+			self.deserialize_scalar(first)
+		};
+		if let Err(JsonItError::SerdeError { error, .. }) = &item {
+			// only a value of the wrong shape lets us go on, after skipping the rest of the item
+			if !error.is_data() || (in_source && !self.reader.skip_value()) {
+				self.state = State::Ended;
+			}
+		}
+		item
+	}
+
+	/// Numbers and literals have no closing delimiter, so serde would consume the byte following them,
+	/// which can be the end of the array. We collect them ourselves and leave that byte for the next read,
+	/// only giving serde a copy of it to tell where the value ends.
+	fn deserialize_scalar<T: Deserialize<'de>>(&mut self, first: u8) -> Result<T, JsonItError> {
+		let start = self.reader.position();
+		let mut token = vec![first];
+		// the end of the stream will be reported by the next read
+		let mut next = None;
+		while let Ok(Some(c)) = self.reader.peek() {
+			if !is_scalar_byte(c) {
+				next = Some(c);
+				break;
+			}
+			token.push(c);
+			self.reader.consume(1);
+		}
+		if let Some(c) = next {
+			token.push(c);
+		}
+		let (value, stopped, whole) = deserialize_token(&token, start);
+		value.map_err(|e| {
+			// a malformed literal can not be skipped to read the next items
+			if !whole {
+				self.state = State::Ended;
+			}
+			self.serde_error_at(stopped, e)
+		})
+	}
+
+	/// Skips whitespace, giving the next significant byte
+	fn next_significant_char(&mut self) -> Result<u8, JsonItError> {
+		loop {
+			let c = self.next_char()?;
+			if !is_whitespace(c) {
+				return Ok(c);
+			}
+		}
+	}
+
+	/// Reads the key of an object member, its opening quote being consumed,
+	/// up to the first byte of its value
+	fn read_key(&mut self) -> Result<u8, JsonItError> {
+		let mut raw = vec![b'"'];
+		let mut escape = false;
+		loop {
+			let c = self.next_char()?;
+			raw.push(c);
+			if c == b'"' && !escape {
+				break;
+			}
+			escape = !escape && c == b'\\';
+		}
+		self.key = serde_json::from_slice(&raw).map_err(|e| self.serde_error(e))?;
+		match self.next_significant_char()? {
+			b':' => self.next_significant_char(),
+			c => Err(self.unexpected(Unexpected(c))),
+		}
+	}
+
+	/// Skips the bytes which can not change the state of the scanner, a buffer at a time
+	fn fast_forward(&mut self) -> Result<(), JsonItError> {
+		loop {
+			let bytes = match self.reader.fill_buf() {
+				Ok(bytes) => bytes,
+				Err(error) => {
+					return Err(JsonItError::IoError {
+						error,
+						location: self.location(),
+					})
+				}
+			};
+			let skipped = self.scanner.skip(bytes);
+			if skipped == 0 {
+				return Ok(());
+			}
+			self.reader.skip(skipped);
+		}
+	}
+
+	/// Advances to the first value designated by one of the paths, without streaming its content.
+	/// Gives the position of this path and the first byte of the value, or [None] if the document ends first
+	fn find_value<P: Borrow<Path>>(&mut self, paths: &[P]) -> Result<Option<(usize, u8)>, JsonItError> {
+		loop {
+			self.fast_forward()?;
+			let c = match self.next_char() {
+				Err(JsonItError::IoError { error, .. }) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+					return Ok(None)
+				}
+				c => c?,
+			};
+			match self.scanner.feed(c, paths).map_err(|e| self.unexpected(e))? {
+				Event::Found(found) => return Ok(Some((found, c))),
+				Event::Done => return Ok(None),
+				Event::None => {}
+			}
+		}
+	}
+
+	/// Ends the iterator once the whole document was searched,
+	/// with an error if a path was never found, unless [ItemReader::missing_as_empty] is set
+	fn end_search<P: Borrow<Path>>(&mut self, paths: &[P]) -> Option<Result<(usize, u8), JsonItError>> {
+		self.state = State::Ended;
+		if self.missing_as_empty {
+			return None;
+		}
+		let missing = paths.iter().zip(&self.found).find(|(_, found)| !**found)?;
+		Some(Err(JsonItError::PathNotFound {
+			path: missing.0.borrow().clone(),
+			location: self.location(),
+		}))
+	}
+
+	/// Tells if no other array has to be searched: every path designates a single array, which was found already
+	fn is_finished<P: Borrow<Path>>(&self, paths: &[P]) -> bool {
+		paths
+			.iter()
+			.enumerate()
+			.all(|(i, path)| !path.borrow().matches_many() && self.found.get(i).copied().unwrap_or(false))
+	}
+
+	/// Advances to the next item to return, selected by one of the paths.
+	/// Gives the position of this path and the first byte of the item,
+	/// the item itself must then be read with [ItemReader::deserialize_item]
+	pub(crate) fn next_item<P: Borrow<Path>>(&mut self, paths: &[P]) -> Option<Result<(usize, u8), JsonItError>> {
+		let item = self.find_item(paths);
+		if let Some(Err(e)) = &item {
+			// we can not know where we are in a malformed document, or after a failed read
+			if !matches!(e, JsonItError::NotAnArray { .. } | JsonItError::NotAnObject { .. }) {
+				self.state = State::Ended;
+			}
+		}
+		item
+	}
+
+	/// The next item at the single path of an iterator, deserialized
+	pub(crate) fn next_at<T: Deserialize<'de>>(&mut self, path: &Path) -> Option<Result<T, JsonItError>> {
+		match self.next_item(std::slice::from_ref(&path))? {
+			Ok((_, first)) => Some(self.deserialize_item(first)),
+			Err(e) => Some(Err(e)),
+		}
+	}
+
+	fn find_item<P: Borrow<Path>>(&mut self, paths: &[P]) -> Option<Result<(usize, u8), JsonItError>> {
+		loop {
+			match self.state {
+				State::InvalidPath(_) => {
+					if let State::InvalidPath(e) = std::mem::replace(&mut self.state, State::Ended) {
+						return Some(Err(JsonItError::InvalidPath(e)));
+					}
+				}
+				State::NotStarted => {
+					if self.found.len() < paths.len() {
+						self.found.resize(paths.len(), false);
+					}
+					if let Err(e) = self.fast_forward() {
+						return Some(Err(e));
+					}
+					let c = match self.next_char() {
+						// the document ended before every path was found
+						Err(JsonItError::IoError { error, .. })
+							if error.kind() == std::io::ErrorKind::UnexpectedEof =>
+						{
+							return self.end_search(paths);
+						}
 						Err(e) => return Some(Err(e)),
-						Ok(c) => {
-							if stack_dirty {
-								stack_dirty = false;
-								if compare_stack_reader(&key_stack, path_to_look_for) {
-									self.state = State::Started;
-									// advance until we get the array
-									let r = loop {
-										match self.next_char() {
-											// should have err
-											Err(e) => {
-												break Err(e);
-											}
-											Ok(c) => match c {
-												b'[' => break Ok(()),
-												_ => {
-													continue;
-												}
-											},
-										};
-									};
-									if let Ok(next) = self.next_char() {
-										// handle if array is empty
-										if next == b']' {
-											return None;
-										}
-										if r.is_ok() {
-											return Some(self.deserialize_one_item(Some(next)));
-										}
-									}
-								}
+						Ok(c) => c,
+					};
+					self.item = None;
+					let event = match self.scanner.feed(c, paths) {
+						// with wildcards, only the matching values of the searched kind are streamed
+						Ok(Event::Found(found))
+							if c != self.container.start() && paths[found].borrow().matches_many() =>
+						{
+							self.scanner.skip_found(c)
+						}
+						event => event,
+					};
+					match event {
+						Err(e) => return Some(Err(self.unexpected(e))),
+						Ok(Event::Found(found)) if c != self.container.start() => {
+							self.found[found] = true;
+							let location = self.location();
+							if self.is_finished(paths) {
+								// nothing else to look for in the rest of the document
+								self.state = State::Ended;
+							} else if let Err(e) = self.scanner.skip_found(c) {
+								return Some(Err(self.unexpected(e)));
 							}
-
-							match state {
-								// handle current key count
-								NotStartedState::ParseObjectKey => {
-									if c == b'\"' && !escape {
-										state = NotStartedState::ExpectPoints;
-										// TODO: should avoid cloning
-										key_stack.push(std::mem::take(&mut current_key));
-										stack_dirty = true;
-									} else {
-										current_key.push(c);
-									}
-								}
-								NotStartedState::ParseValue(t) => {
-									// detect end of value
-									match t {
-										ParseValueType::String => {
-											if c == b'\"' && !escape {
-												state = NotStartedState::ParseObject;
-												key_stack.pop();
-												stack_dirty = true;
-											}
-											if escape {
-												escape = false;
-											}
-										}
-										ParseValueType::Array => {
-											if c == b']' {
-												state = NotStartedState::ParseObject;
-												key_stack.pop();
-												stack_dirty = true;
-											}
-										}
-										ParseValueType::Number => {
-											if c == b',' {
-												state = NotStartedState::ParseObject;
-												key_stack.pop();
-												stack_dirty = true;
-											}
-										}
-										ParseValueType::Null => {
-											if c == b',' {
-												state = NotStartedState::ParseObject;
-												key_stack.pop();
-												stack_dirty = true;
-											}
-										}
-										ParseValueType::Map => {
-											// key_stack.push(current_key.clone());
-											// current_key.clear();
-											state = NotStartedState::ParseObject;
-										}
-									};
-								}
-								NotStartedState::ExpectValue => {
-									if c == b'\"' {
-										state = NotStartedState::ParseValue(ParseValueType::String);
-									} else if c == b'n' {
-										// speculative nominal value is null
-										state = NotStartedState::ParseValue(ParseValueType::Null);
-									} else if c == b'{' {
-										state = NotStartedState::ParseValue(ParseValueType::Map);
-									} else if c == b'[' {
-										state = NotStartedState::ParseValue(ParseValueType::Array);
-									} else if c == b' ' {
-									} else {
-										state = NotStartedState::ParseValue(ParseValueType::Number);
-									}
-								}
-								NotStartedState::ExpectPoints => {
-									if c == b':' {
-										state = NotStartedState::ExpectValue;
-									}
-								}
-								NotStartedState::ParseObject => {
-									if c == b'\"' {
-										state = NotStartedState::ParseObjectKey;
-									}
-									if c == b'}' {
-										key_stack.pop();
-										stack_dirty = true;
-									}
-								}
-								NotStartedState::None => {
-									if c == b'{' {
-										// start root of object
-										state = NotStartedState::ParseObject;
-									} else if c == b'[' {
-										todo!("arrays are unsupported for now");
-									} else if c != b' ' {
-										panic!("malformed");
-									}
-								}
-							};
+							let found = ValueKind::of(c);
+							return Some(Err(match self.container {
+								Container::Array => JsonItError::NotAnArray { found, location },
+								Container::Object => JsonItError::NotAnObject { found, location },
+							}));
+						}
+						Ok(Event::Found(found)) => {
+							// the opening bracket of the searched array has been consumed
+							self.state = State::Started;
+							self.current = found;
+							self.item_index = 0;
+							self.expect = Expect::First;
+							self.found[found] = true;
 						}
+						Ok(Event::Done) => return self.end_search(paths),
+						Ok(Event::None) => {}
 					}
 				}
-			}
-			State::Started => loop {
-				break match self.next_char() {
-					Err(e) => Some(Err(e)),
-					Ok(c) => match c {
-						b']' => {
-							self.state = State::Ended;
-							None
+				State::Started => {
+					let c = match self.next_char() {
+						Err(e) => return Some(Err(e)),
+						Ok(c) => c,
+					};
+					let path = paths[self.current].borrow();
+					let first = match c {
+						w if is_whitespace(w) => continue,
+						// a trailing comma is not allowed
+						c if c == self.container.end() && self.expect != Expect::Next => {
+							self.scanner.value_consumed();
+							// look for the next matching struct
+							if self.is_finished(paths) {
+								self.state = State::Ended;
+							} else if self.scanner.is_done() {
+								return self.end_search(paths);
+							} else {
+								self.state = State::NotStarted;
+							}
+							continue;
 						}
-						b',' => {
-							// Parse with serde_json
-							Some(self.deserialize_one_item(None))
+						// separates items
+						b',' if self.expect == Expect::After => {
+							self.expect = Expect::Next;
+							continue;
 						}
-						w => {
-							if w.is_ascii_whitespace() {
-								continue;
-							} else if w.is_ascii_digit() || w == b'n' {
-								// n for null
-								// handle serde eating one too many char
-								// deserialyze number
-								Some(self.deserialize_one_item(Some(w)))
-							} else if w == b'}' || w == b']' {
-								// suppose end
-								None
-							} else {
-								Some(Err(JsonItError::InvalidJsonCharacter(char::from(w))))
+						// a comma is needed between items
+						c if self.expect == Expect::After => return Some(Err(self.unexpected(Unexpected(c)))),
+						b'"' if self.container == Container::Object => {
+							self.item = Some(self.item_index);
+							match self.read_key() {
+								Ok(first) => first,
+								Err(e) => return Some(Err(e)),
 							}
 						}
-					},
-				};
-			},
-			State::Ended => None,
+						c if self.container == Container::Array => {
+							self.item = Some(self.item_index);
+							c
+						}
+						w => return Some(Err(self.unexpected(Unexpected(w)))),
+					};
+					match first {
+						b'{' | b'[' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n' => {
+							let index = self.item_index;
+							self.item_index += 1;
+							self.expect = Expect::After;
+							if path.selects_item(index) {
+								return Some(Ok((self.current, first)));
+							}
+							if path.is_past_items(index) && self.is_finished(paths) {
+								// no other item is needed, skip the rest of the stream
+								self.state = State::Ended;
+							} else if let Err(e) = self.deserialize_item::<IgnoredAny>(first) {
+								return Some(Err(e));
+							}
+						}
+						w => return Some(Err(self.unexpected(Unexpected(w)))),
+					}
+				}
+				State::Ended => return None,
+			}
 		}
 	}
 }
 
-/// Master error for the [JsonSeqIterator]
-/// Holds error, for [std::io::Error], [serde_json::Error] and [JsonItError::InvalidJsonCharacter]
+/// Master error for the [JsonSeqIterator], the [JsonMultiIterator](crate::JsonMultiIterator)
+/// and [stream_read_items_at](crate::stream_read_items_at).
+/// After an error other than a failed deserialization or a value of the wrong kind, the iterators end
+/// Holds error, for [std::io::Error], [serde_json::Error], [JsonItError::InvalidJsonCharacter],
+/// [JsonItError::InvalidPath], and for paths missing from the document or designating another kind of value
 #[derive(Debug)]
 pub enum JsonItError {
-	SerdeError(serde_json::Error),
-	IoError(std::io::Error),
-	// "[JsonIt] Unexpected character: {}",
-	InvalidJsonCharacter(char),
+	/// Located where serde stopped in the item, at the line and column of its error
+	SerdeError {
+		error: serde_json::Error,
+		location: Box<Location>,
+	},
+	IoError {
+		error: std::io::Error,
+		location: Box<Location>,
+	},
+	InvalidJsonCharacter {
+		found: char,
+		location: Box<Location>,
+	},
+	InvalidPath(PathError),
+	/// The document ended before the path was found
+	PathNotFound {
+		path: Path,
+		location: Box<Location>,
+	},
+	/// The path designates a value which is not an array
+	NotAnArray {
+		found: ValueKind,
+		location: Box<Location>,
+	},
+	/// The path designates a value which is not an object, when streaming its members
+	NotAnObject {
+		found: ValueKind,
+		location: Box<Location>,
+	},
+}
+
+impl JsonItError {
+	/// Where the error happened in the document, [None] for an invalid path
+	pub fn location(&self) -> Option<&Location> {
+		match self {
+			Self::SerdeError { location, .. }
+			| Self::IoError { location, .. }
+			| Self::InvalidJsonCharacter { location, .. }
+			| Self::PathNotFound { location, .. }
+			| Self::NotAnArray { location, .. }
+			| Self::NotAnObject { location, .. } => Some(location.as_ref()),
+			Self::InvalidPath(_) => None,
+		}
+	}
+
+	/// Classifies the error, to tell if reading again, skipping the item or fixing the path can help
+	pub fn kind(&self) -> ErrorKind {
+		match self {
+			Self::SerdeError { error, .. } => match error.classify() {
+				serde_json::error::Category::Io => ErrorKind::Io,
+				serde_json::error::Category::Data => ErrorKind::Data,
+				_ if error.to_string().starts_with(RECURSION_LIMIT) => ErrorKind::Limit,
+				serde_json::error::Category::Syntax | serde_json::error::Category::Eof => ErrorKind::Syntax,
+			},
+			Self::IoError { .. } => ErrorKind::Io,
+			Self::InvalidJsonCharacter { .. } => ErrorKind::Syntax,
+			Self::InvalidPath(_) | Self::PathNotFound { .. } => ErrorKind::Path,
+			Self::NotAnArray { .. } | Self::NotAnObject { .. } => ErrorKind::Data,
+		}
+	}
+}
+
+/// How serde_json's errors start when a value is too deeply nested, its error codes being private.
+/// The same from serde_json 1.0.108 to 1.0.154 at least, checked by the test below
+const RECURSION_LIMIT: &str = "recursion limit exceeded";
+
+/// The category of a [JsonItError], given by [JsonItError::kind]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum ErrorKind {
+	/// The document could not be read
+	Io,
+	/// The document is not valid JSON, or was truncated
+	Syntax,
+	/// A value does not have the expected shape, the next items can still be read
+	Data,
+	/// The path is invalid, or missing from the document
+	Path,
+	/// The document is too deeply nested
+	Limit,
+}
+
+impl fmt::Display for JsonItError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::SerdeError { location, .. } => write!(f, "[JsonIt] Could not deserialize the value{}", At(location)),
+			Self::IoError { location, .. } => write!(f, "[JsonIt] Could not read the document{}", At(location)),
+			Self::InvalidJsonCharacter { found, location } => {
+				write!(f, "[JsonIt] Unexpected character: {:?}{}", found, At(location))
+			}
+			Self::InvalidPath(e) => write!(f, "[JsonIt] {}", e),
+			Self::PathNotFound { path, location } => write!(f, "[JsonIt] Path not found: {}{}", path, At(location)),
+			Self::NotAnArray { found, location } => {
+				write!(f, "[JsonIt] Expected an array, found {}{}", found, At(location))
+			}
+			Self::NotAnObject { found, location } => {
+				write!(f, "[JsonIt] Expected an object, found {}{}", found, At(location))
+			}
+		}
+	}
+}
+
+/// Writes a location, unless it is unknown
+struct At<'a>(&'a Location);
+
+impl fmt::Display for At<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if *self.0 == Location::default() {
+			return Ok(());
+		}
+		write!(f, " at {}", self.0)
+	}
+}
+
+impl std::error::Error for JsonItError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::SerdeError { error, .. } => Some(error),
+			Self::IoError { error, .. } => Some(error),
+			// the error of the path is part of the message
+			_ => None,
+		}
+	}
+}
+
+// the converted errors have no location
+impl From<std::io::Error> for JsonItError {
+	fn from(error: std::io::Error) -> Self {
+		Self::IoError {
+			error,
+			location: Box::default(),
+		}
+	}
+}
+
+impl From<serde_json::Error> for JsonItError {
+	fn from(error: serde_json::Error) -> Self {
+		Self::SerdeError {
+			error,
+			location: Box::default(),
+		}
+	}
+}
+
+impl From<PathError> for JsonItError {
+	fn from(e: PathError) -> Self {
+		Self::InvalidPath(e)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn recursion_limit_message() {
+		let nested = format!("{}{}", "[".repeat(200), "]".repeat(200));
+		let error = serde_json::from_str::<serde_json::Value>(&nested).unwrap_err();
+		assert!(error.to_string().starts_with(RECURSION_LIMIT), "{}", error);
+		let error = serde_json::from_reader::<_, serde_json::Value>(nested.as_bytes()).unwrap_err();
+		assert!(error.to_string().starts_with(RECURSION_LIMIT), "{}", error);
+	}
 }
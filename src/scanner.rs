@@ -0,0 +1,365 @@
+use std::borrow::Borrow;
+use std::fmt;
+
+use crate::path::{Path, Segment};
+
+/// One level of nesting the scanner is currently in
+#[derive(Debug)]
+enum Frame {
+	/// Holds the key of the member being parsed, decoded once read
+	Object(Vec<u8>),
+	/// Holds the index of the element being parsed
+	Array(usize),
+}
+
+impl Frame {
+	fn matches(&self, segment: &Segment) -> bool {
+		match (self, segment) {
+			(_, Segment::Wildcard) => true,
+			(Frame::Object(key), Segment::Key(expected)) => key == expected.as_bytes(),
+			(Frame::Array(index), Segment::Index(expected)) => index == expected,
+			(Frame::Object(key), Segment::Index(expected)) => is_decimal(key, *expected),
+			(Frame::Array(index), Segment::Slice(slice)) => slice.contains(*index),
+			_ => false,
+		}
+	}
+}
+
+/// Tells if a key is the given number written in decimal, without leading zero
+fn is_decimal(key: &[u8], mut number: usize) -> bool {
+	let mut i = key.len();
+	loop {
+		if i == 0 || key[i - 1] != b'0' + (number % 10) as u8 {
+			return false;
+		}
+		i -= 1;
+		number /= 10;
+		if number == 0 {
+			return i == 0;
+		}
+	}
+}
+
+/// The whitespace allowed between tokens by RFC 8259, unlike form feeds
+pub(crate) fn is_whitespace(c: u8) -> bool {
+	matches!(c, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// The bytes of a number or a literal, up to the one ending it
+pub(crate) fn is_scalar_byte(c: u8) -> bool {
+	c.is_ascii_alphanumeric() || c == b'.' || c == b'+' || c == b'-'
+}
+
+/// Tells if the location given by the frames is designated by the segments
+fn matches(frames: &[Frame], segments: &[Segment]) -> bool {
+	match segments.split_first() {
+		None => frames.is_empty(),
+		// try every number of levels for the recursive descent
+		Some((Segment::Descendants, rest)) => (0..=frames.len()).any(|skipped| matches(&frames[skipped..], rest)),
+		Some((segment, rest)) => match frames.split_first() {
+			Some((frame, frames)) => frame.matches(segment) && matches(frames, rest),
+			None => false,
+		},
+	}
+}
+
+/// Decodes the escape sequences of a string without its quotes, including `\uXXXX` and surrogate pairs
+fn unescape(raw: &[u8]) -> Option<Vec<u8>> {
+	let mut quoted = Vec::with_capacity(raw.len() + 2);
+	quoted.push(b'"');
+	quoted.extend_from_slice(raw);
+	quoted.push(b'"');
+	serde_json::from_slice::<String>(&quoted).ok().map(String::into_bytes)
+}
+
+fn is_value_start(c: u8) -> bool {
+	matches!(c, b'[' | b'{' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n')
+}
+
+/// The kind of a JSON value
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum ValueKind {
+	Object,
+	Array,
+	String,
+	Number,
+	Boolean,
+	Null,
+}
+
+impl ValueKind {
+	/// The kind of the value starting with this byte
+	pub(crate) fn of(first: u8) -> Self {
+		match first {
+			b'{' => Self::Object,
+			b'[' => Self::Array,
+			b'"' => Self::String,
+			b't' | b'f' => Self::Boolean,
+			b'n' => Self::Null,
+			_ => Self::Number,
+		}
+	}
+}
+
+impl fmt::Display for ValueKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Object => "object",
+			Self::Array => "array",
+			Self::String => "string",
+			Self::Number => "number",
+			Self::Boolean => "boolean",
+			Self::Null => "null",
+		})
+	}
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum ScanState {
+	/// A value is expected, at the start of the document, after ":" or after "," in an array
+	Value,
+	/// Right after "[", a value or "]" is expected
+	FirstValue,
+	/// Right after "{", a key or "}" is expected
+	FirstKey,
+	/// After "," in an object, a key is expected
+	Key,
+	InKey,
+	/// We expect ":" or whitespace
+	ExpectPoints,
+	InString,
+	/// Inside a number or a literal
+	InScalar,
+	/// After a value, we expect "," or the end of the enclosing struct
+	AfterValue,
+	/// The root value has been fully read
+	Done,
+}
+
+/// The kind of struct whose content is streamed
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Container {
+	/// The elements of an array
+	Array,
+	/// The members of an object
+	Object,
+}
+
+impl Container {
+	pub fn start(&self) -> u8 {
+		match self {
+			Self::Array => b'[',
+			Self::Object => b'{',
+		}
+	}
+
+	pub fn end(&self) -> u8 {
+		match self {
+			Self::Array => b']',
+			Self::Object => b'}',
+		}
+	}
+}
+
+/// A byte which can not appear at this place of a JSON document
+#[derive(PartialEq, Debug)]
+pub struct Unexpected(pub u8);
+
+/// What happened when feeding a byte to the [Scanner]
+#[derive(PartialEq, Debug)]
+pub enum Event {
+	None,
+	/// The first byte of a value designated by the path at this position was just consumed.
+	/// The caller is now responsible for reading the value, up to its end,
+	/// and should then call [Scanner::value_consumed], or give the byte back with [Scanner::skip_found]
+	Found(usize),
+	/// The root value has been fully read
+	Done,
+}
+
+/// Keeps track of where we are in the document while looking for a [Path](crate::Path),
+/// skipping every value that is not designated by it
+pub struct Scanner {
+	frames: Vec<Frame>,
+	state: ScanState,
+	// Keeps state if the next character is escaped
+	escape: bool,
+}
+
+impl Scanner {
+	pub fn new() -> Self {
+		Self {
+			frames: vec![],
+			state: ScanState::Value,
+			escape: false,
+		}
+	}
+
+	/// Where we are in the document
+	pub fn path(&self) -> Path {
+		self.frames.iter().fold(Path::root(), |path, frame| match frame {
+			Frame::Object(key) => path.key(String::from_utf8_lossy(key)),
+			Frame::Array(index) => path.index(*index),
+		})
+	}
+
+	pub fn is_done(&self) -> bool {
+		self.state == ScanState::Done
+	}
+
+	/// To be called once the caller has read the value announced by [Event::Found]
+	pub fn value_consumed(&mut self) {
+		self.state = self.after_value();
+	}
+
+	fn after_value(&self) -> ScanState {
+		if self.frames.is_empty() {
+			ScanState::Done
+		} else {
+			ScanState::AfterValue
+		}
+	}
+
+	fn end_value(&mut self) -> Event {
+		self.state = self.after_value();
+		if self.state == ScanState::Done {
+			Event::Done
+		} else {
+			Event::None
+		}
+	}
+
+	/// Gives the position of the first path designating the current location
+	fn find<P: Borrow<Path>>(&self, paths: &[P]) -> Option<usize> {
+		paths
+			.iter()
+			.position(|path| matches(&self.frames, path.borrow().segments()))
+	}
+
+	pub fn feed<P: Borrow<Path>>(&mut self, c: u8, paths: &[P]) -> Result<Event, Unexpected> {
+		if matches!(self.state, ScanState::Value | ScanState::FirstValue) && is_value_start(c) {
+			if let Some(found) = self.find(paths) {
+				return Ok(Event::Found(found));
+			}
+		}
+		self.step(c)
+	}
+
+	/// Gives how many of the first bytes can be skipped without feeding them, as they can not change the state:
+	/// the content of strings, found with a vectorized search, scalars and whitespace.
+	/// The skipped bytes hold no quote, backslash nor bracket, the skipped bytes of a key are kept
+	pub fn skip(&mut self, bytes: &[u8]) -> usize {
+		if self.escape {
+			return 0;
+		}
+		match self.state {
+			ScanState::InString => memchr::memchr2(b'"', b'\\', bytes).unwrap_or(bytes.len()),
+			ScanState::InKey => {
+				let skipped = memchr::memchr2(b'"', b'\\', bytes).unwrap_or(bytes.len());
+				if let Some(Frame::Object(key)) = self.frames.last_mut() {
+					key.extend_from_slice(&bytes[..skipped]);
+				}
+				skipped
+			}
+			ScanState::InScalar => bytes.iter().position(|c| !is_scalar_byte(*c)).unwrap_or(bytes.len()),
+			_ => bytes.iter().position(|c| !is_whitespace(*c)).unwrap_or(bytes.len()),
+		}
+	}
+
+	/// Reads the value announced by [Event::Found] as any other value, when the caller does not want it
+	pub fn skip_found(&mut self, c: u8) -> Result<Event, Unexpected> {
+		self.step(c)
+	}
+
+	fn step(&mut self, c: u8) -> Result<Event, Unexpected> {
+		match self.state {
+			ScanState::Value | ScanState::FirstValue => match c {
+				b'[' => {
+					self.frames.push(Frame::Array(0));
+					self.state = ScanState::FirstValue;
+				}
+				b'{' => {
+					self.frames.push(Frame::Object(vec![]));
+					self.state = ScanState::FirstKey;
+				}
+				b']' if self.state == ScanState::FirstValue => {
+					self.frames.pop();
+					return Ok(self.end_value());
+				}
+				b'"' => self.state = ScanState::InString,
+				b'-' | b'0'..=b'9' | b't' | b'f' | b'n' => self.state = ScanState::InScalar,
+				c if is_whitespace(c) => {}
+				c => return Err(Unexpected(c)),
+			},
+			ScanState::FirstKey | ScanState::Key => match c {
+				b'"' => {
+					if let Some(Frame::Object(key)) = self.frames.last_mut() {
+						key.clear();
+					}
+					self.state = ScanState::InKey;
+				}
+				b'}' if self.state == ScanState::FirstKey => {
+					self.frames.pop();
+					return Ok(self.end_value());
+				}
+				c if is_whitespace(c) => {}
+				c => return Err(Unexpected(c)),
+			},
+			ScanState::InKey => {
+				if c == b'"' && !self.escape {
+					self.state = ScanState::ExpectPoints;
+					// keys are matched on their text
+					if let Some(Frame::Object(key)) = self.frames.last_mut() {
+						if key.contains(&b'\\') {
+							*key = unescape(key).ok_or(Unexpected(b'\\'))?;
+						}
+					}
+				} else {
+					self.escape = !self.escape && c == b'\\';
+					if let Some(Frame::Object(key)) = self.frames.last_mut() {
+						key.push(c);
+					}
+				}
+			}
+			ScanState::ExpectPoints => match c {
+				b':' => self.state = ScanState::Value,
+				c if is_whitespace(c) => {}
+				c => return Err(Unexpected(c)),
+			},
+			ScanState::InString => {
+				if c == b'"' && !self.escape {
+					return Ok(self.end_value());
+				}
+				self.escape = !self.escape && c == b'\\';
+			}
+			ScanState::InScalar => {
+				if !is_scalar_byte(c) {
+					// the byte after a scalar belongs to what follows it
+					if self.end_value() == Event::Done {
+						return Ok(Event::Done);
+					}
+					return self.step(c);
+				}
+			}
+			ScanState::AfterValue => match (c, self.frames.last_mut()) {
+				(b',', Some(Frame::Array(index))) => {
+					*index += 1;
+					self.state = ScanState::Value;
+				}
+				(b',', Some(Frame::Object(_))) => self.state = ScanState::Key,
+				(b']', Some(Frame::Array(_))) | (b'}', Some(Frame::Object(_))) => {
+					self.frames.pop();
+					return Ok(self.end_value());
+				}
+				(c, _) if is_whitespace(c) => {}
+				(c, _) => return Err(Unexpected(c)),
+			},
+			ScanState::Done => {
+				if !is_whitespace(c) {
+					return Err(Unexpected(c));
+				}
+			}
+		}
+		Ok(Event::None)
+	}
+}
@@ -0,0 +1,424 @@
+use std::cell::{Cell, RefCell};
+use std::io::{self, BufRead, Read};
+
+use serde::de::{Deserialize, IgnoredAny};
+
+use crate::encoding::Decoder;
+use crate::location::Position;
+use crate::scanner::is_scalar_byte;
+use crate::watched::{Counting, Given, Watched};
+
+/// Where the [ItemReader](crate::reader::ItemReader) reads the document from,
+/// giving its bytes and deserializing the values in it, borrowed from it when it is in memory
+pub(crate) trait Source<'de> {
+	/// The bytes not read yet, empty at the end of the document
+	fn fill_buf(&mut self) -> io::Result<&[u8]>;
+
+	/// Reads bytes given by [Source::fill_buf]
+	fn consume(&mut self, amount: usize);
+
+	/// Skips bytes given by [Source::fill_buf], which must hold no quote, backslash nor bracket
+	fn skip(&mut self, amount: usize);
+
+	/// Where we are in the document
+	fn position(&self) -> Position;
+
+	/// Whether [Source::deserialize] also takes numbers and literals, knowing where they end
+	const SCALARS: bool = false;
+
+	/// Deserializes the value whose first byte was just read, a bracket or a quote unless [Source::SCALARS]
+	fn deserialize<T: Deserialize<'de>>(&mut self, first: u8) -> Result<T, serde_json::Error>;
+
+	/// Where serde stopped on the last error of [Source::deserialize]
+	fn error_position(&self) -> Position;
+
+	/// Skips the rest of the value whose deserialization failed, telling if it could be done
+	fn skip_value(&mut self) -> bool;
+
+	/// Gives the next byte without reading it, [None] at the end of the document
+	#[inline]
+	fn peek(&mut self) -> io::Result<Option<u8>> {
+		Ok(self.fill_buf()?.first().copied())
+	}
+
+	/// Reads the next byte, [None] at the end of the document
+	#[inline]
+	fn next_byte(&mut self) -> io::Result<Option<u8>> {
+		let c = self.peek()?;
+		if c.is_some() {
+			self.consume(1);
+		}
+		Ok(c)
+	}
+}
+
+/// The source of the iterators reading a [Read], in any encoding
+pub(crate) type Buffered<R> = Counted<Decoder<R>>;
+
+/// The size of the buffers of [Counted] and of its [Decoder],
+/// the reader given by the user being read in chunks of this size
+pub(crate) const BUFFER_SIZE: usize = 64 * 1024;
+
+/// A buffered [Read] keeping the [Position] of the bytes read from it, including the ones read by serde,
+/// and how deeply they are nested in arrays and objects
+pub(crate) struct Counted<R> {
+	reader: R,
+	buffer: Box<[u8]>,
+	/// The bytes of the buffer not read yet
+	start: usize,
+	end: usize,
+	position: Position,
+	/// Where serde stopped in this value, before the byte it peeked
+	stopped: Position,
+	depth: usize,
+	/// The depth of the value being deserialized, to skip its end after an error
+	value_depth: usize,
+	in_string: bool,
+	escape: bool,
+}
+
+impl<R> Counted<R> {
+	pub(crate) fn new(reader: R) -> Self {
+		Self {
+			reader,
+			buffer: vec![0; BUFFER_SIZE].into_boxed_slice(),
+			start: 0,
+			end: 0,
+			position: Position::default(),
+			stopped: Position::default(),
+			depth: 0,
+			value_depth: 0,
+			in_string: false,
+			escape: false,
+		}
+	}
+
+	fn count(&mut self, c: u8) {
+		self.position.advance(c);
+		if self.in_string {
+			self.in_string = self.escape || c != b'"';
+			self.escape = !self.escape && c == b'\\';
+			return;
+		}
+		match c {
+			b'"' => self.in_string = true,
+			b'[' | b'{' => self.depth += 1,
+			b']' | b'}' => self.depth = self.depth.saturating_sub(1),
+			_ => {}
+		}
+	}
+}
+
+impl<'de, R: Read> Source<'de> for Counted<R> {
+	fn fill_buf(&mut self) -> io::Result<&[u8]> {
+		BufRead::fill_buf(self)
+	}
+
+	fn consume(&mut self, amount: usize) {
+		BufRead::consume(self, amount)
+	}
+
+	fn skip(&mut self, amount: usize) {
+		let end = self.start + amount;
+		self.position.advance_all(&self.buffer[self.start..end]);
+		self.start = end;
+	}
+
+	fn position(&self) -> Position {
+		self.position
+	}
+
+	fn deserialize<T: Deserialize<'de>>(&mut self, first: u8) -> Result<T, serde_json::Error> {
+		// the opening bracket of the value is read already
+		let position = self.position;
+		self.value_depth = match first {
+			b'"' => self.depth,
+			_ => self.depth.saturating_sub(1),
+		};
+		let r = &[first][..];
+		let (value, stopped) = deserialize_delimited(r.chain(&mut *self), position);
+		self.stopped = stopped;
+		value
+	}
+
+	fn error_position(&self) -> Position {
+		self.stopped
+	}
+
+	fn skip_value(&mut self) -> bool {
+		while self.depth > self.value_depth {
+			if !matches!(self.next_byte(), Ok(Some(_))) {
+				return false;
+			}
+		}
+		true
+	}
+}
+
+impl<R: Read> Read for Counted<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let available = BufRead::fill_buf(self)?;
+		let read = available.len().min(buf.len());
+		buf[..read].copy_from_slice(&available[..read]);
+		BufRead::consume(self, read);
+		Ok(read)
+	}
+}
+
+impl<R: Read> BufRead for Counted<R> {
+	fn fill_buf(&mut self) -> io::Result<&[u8]> {
+		while self.start == self.end {
+			match self.reader.read(&mut self.buffer) {
+				Ok(read) => {
+					self.start = 0;
+					self.end = read;
+					if read == 0 {
+						break;
+					}
+				}
+				Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+				Err(e) => return Err(e),
+			}
+		}
+		Ok(&self.buffer[self.start..self.end])
+	}
+
+	fn consume(&mut self, amount: usize) {
+		let end = self.start + amount;
+		for i in self.start..end {
+			self.count(self.buffer[i]);
+		}
+		self.start = end;
+	}
+}
+
+/// Deserializes a string, an array or an object from a [Read], serde reading up to its closing delimiter,
+/// the first byte of the value being at `first`.
+/// Also gives where serde stopped on an error, as if it read from a slice, or where the value ends
+pub(crate) fn deserialize_delimited<'de, T: Deserialize<'de>>(
+	reader: impl Read,
+	first: Position,
+) -> (Result<T, serde_json::Error>, Position) {
+	let given = RefCell::new(Given::default());
+	let mut deserializer = serde_json::Deserializer::from_reader(Counting { reader, given: &given });
+	let value = T::deserialize(Watched {
+		inner: &mut deserializer,
+		given: &given,
+	});
+	let given = given.into_inner();
+	let read = match &value {
+		Ok(_) => given.read,
+		Err(error) => {
+			// like from a slice, an error without a position is on the first byte
+			let read = match error.line() {
+				0 => 1,
+				1 => error.column(),
+				line => given
+					.lines
+					.get(line - 2)
+					.map_or(given.read, |start| start + error.column()),
+			}
+			.min(given.read);
+			// like from a slice, a data error is before the byte serde peeked, but not before the value
+			match given.stopped {
+				Some(stopped) if error.is_data() => read.min(stopped).max(1),
+				_ => read,
+			}
+		}
+	};
+	(value, position_of(&given, first, read))
+}
+
+/// Deserializes a number or a literal from its bytes, followed by the one ending it if any,
+/// the first byte being at `first`.
+/// Also gives where serde stopped on an error, as if it read from a slice,
+/// and if the bytes hold a whole number or literal, so that the items after it can be read
+pub(crate) fn deserialize_token<'de, T: Deserialize<'de>>(
+	token: &[u8],
+	first: Position,
+) -> (Result<T, serde_json::Error>, Position, bool) {
+	let mut end = first;
+	end.advance_all(&token[1..]);
+	let mut values = serde_json::Deserializer::from_reader(token).into_iter();
+	let value = values
+		.next()
+		.unwrap_or_else(|| Err(serde::de::Error::custom("missing value")));
+	let error = match &value {
+		Ok(_) => return (value, end, true),
+		Err(error) => error,
+	};
+	let scalar = token.iter().take_while(|c| is_scalar_byte(**c)).count();
+	let whole = serde_json::from_slice::<IgnoredAny>(&token[..scalar]).is_ok();
+	let mut stopped = first.at_error(end, error);
+	// serde stops at the end of a number, after peeking the byte following it
+	let number = token
+		.iter()
+		.take_while(|c| matches!(c, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E'))
+		.count();
+	if error.is_data() && number > 0 {
+		let mut last = first;
+		last.advance_all(&token[1..number]);
+		if stopped.offset() > last.offset() {
+			stopped = last;
+		}
+	}
+	(value, stopped, whole)
+}
+
+/// The position of the last one of the first `read` bytes given to serde, the first one being at `first`
+fn position_of(given: &Given, first: Position, read: usize) -> Position {
+	// the lines starting before this byte
+	let lines = given.lines.partition_point(|start| *start < read);
+	let line_start = lines.checked_sub(1).map_or(0, |line| given.lines[line]);
+	let after_newline = given.lines.binary_search(&read).is_ok();
+	first.ahead(read.saturating_sub(1), lines, read - line_start, after_newline)
+}
+
+/// The number of bytes before the line and column of an error of serde, in the given bytes
+fn offset_of(bytes: &[u8], error: &serde_json::Error) -> usize {
+	let line_start = match error.line() {
+		0 | 1 => 0,
+		line => memchr::memchr_iter(b'\n', bytes)
+			.nth(line - 2)
+			.map_or(bytes.len(), |newline| newline + 1),
+	};
+	(line_start + error.column()).clamp(1, bytes.len())
+}
+
+/// Bytes in memory, whose values are deserialized in place
+pub(crate) trait Document<'de>: AsRef<[u8]> {
+	/// Deserializes the value starting at this offset, giving the number of bytes read,
+	/// up to the end of the value or to the error
+	fn deserialize_at<T: Deserialize<'de>>(&self, start: usize) -> (Result<T, serde_json::Error>, usize);
+}
+
+/// Deserializes the first value of a stream over the given bytes, giving the number of bytes read,
+/// up to the end of the value or to the error
+fn deserialize_first<'de, R: serde_json::de::Read<'de>, T: Deserialize<'de>>(
+	mut values: serde_json::StreamDeserializer<'de, R, T>,
+	bytes: &[u8],
+) -> (Result<T, serde_json::Error>, usize) {
+	// the first byte of the value is there
+	match values.next() {
+		Some(Ok(value)) => (Ok(value), values.byte_offset()),
+		Some(Err(error)) => {
+			let read = offset_of(bytes, &error);
+			(Err(error), read)
+		}
+		None => (Err(serde::de::Error::custom("missing value")), 0),
+	}
+}
+
+/// Borrowed bytes, outliving the values, which can borrow from them
+impl<'de> Document<'de> for &'de [u8] {
+	fn deserialize_at<T: Deserialize<'de>>(&self, start: usize) -> (Result<T, serde_json::Error>, usize) {
+		let bytes = &self[start..];
+		deserialize_first(serde_json::Deserializer::from_slice(bytes).into_iter(), bytes)
+	}
+}
+
+/// Owned bytes, copied by the values as they can not borrow from them
+#[cfg(feature = "mmap")]
+impl<'de> Document<'de> for memmap2::Mmap {
+	fn deserialize_at<T: Deserialize<'de>>(&self, start: usize) -> (Result<T, serde_json::Error>, usize) {
+		let bytes = &self[start..];
+		let mut first = Position::default();
+		first.advance(bytes[0]);
+		if !matches!(bytes[0], b'[' | b'{' | b'"') {
+			let scalar = bytes.iter().take_while(|c| is_scalar_byte(**c)).count();
+			let (value, stopped, _) = deserialize_token(&bytes[..bytes.len().min(scalar + 1)], first);
+			// the byte ending it is read by the next value
+			let read = match &value {
+				Ok(_) => scalar,
+				Err(_) => stopped.offset() as usize,
+			};
+			return (value, read);
+		}
+		let (value, stopped) = deserialize_delimited(bytes, first);
+		(value, stopped.offset() as usize)
+	}
+}
+
+/// A document in memory, in UTF-8, whose values are deserialized in place
+pub(crate) struct InMemory<D> {
+	data: D,
+	/// The length of the byte order mark, skipped
+	start: usize,
+	/// The number of bytes read, after the byte order mark
+	read: usize,
+	/// Where the value being deserialized starts, to skip it after an error
+	value_start: usize,
+	/// The last position computed and the bytes read then, to count the next one from there
+	counted: Cell<(usize, Position)>,
+}
+
+impl<D: AsRef<[u8]>> InMemory<D> {
+	pub(crate) fn new(data: D) -> Self {
+		let start = if data.as_ref().starts_with(b"\xEF\xBB\xBF") {
+			3
+		} else {
+			0
+		};
+		Self {
+			data,
+			start,
+			read: 0,
+			value_start: 0,
+			counted: Cell::new((0, Position::default())),
+		}
+	}
+
+	fn bytes(&self) -> &[u8] {
+		&self.data.as_ref()[self.start..]
+	}
+}
+
+impl<'de, D: Document<'de>> Source<'de> for InMemory<D> {
+	/// Deserialized in place too, so that they can be borrowed
+	const SCALARS: bool = true;
+
+	fn fill_buf(&mut self) -> io::Result<&[u8]> {
+		Ok(&self.bytes()[self.read..])
+	}
+
+	fn consume(&mut self, amount: usize) {
+		self.read += amount;
+	}
+
+	fn skip(&mut self, amount: usize) {
+		self.read += amount;
+	}
+
+	/// Only computed for errors, from the previous one as the bytes are read forward
+	fn position(&self) -> Position {
+		let (mut counted, mut position) = self.counted.get();
+		if counted > self.read {
+			(counted, position) = (0, Position::default());
+		}
+		position.advance_all(&self.bytes()[counted..self.read]);
+		self.counted.set((self.read, position));
+		position
+	}
+
+	fn deserialize<T: Deserialize<'de>>(&mut self, _first: u8) -> Result<T, serde_json::Error> {
+		self.value_start = self.read - 1;
+		let (value, read) = self.data.deserialize_at(self.start + self.value_start);
+		self.read = self.value_start + read;
+		value
+	}
+
+	/// Read up to the error already
+	fn error_position(&self) -> Position {
+		self.position()
+	}
+
+	fn skip_value(&mut self) -> bool {
+		let mut values =
+			serde_json::Deserializer::from_slice(&self.bytes()[self.value_start..]).into_iter::<IgnoredAny>();
+		let skipped = matches!(values.next(), Some(Ok(_)));
+		if skipped {
+			self.read = self.value_start + values.byte_offset();
+		}
+		skipped
+	}
+}
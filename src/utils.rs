@@ -1,6 +1,9 @@
-use std::io::{Error, Read};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read};
 
 /// Makes a prefix for the [iterator::stream_read_items_at](stream_read_items_at) function
+///
+/// The segments are concatenated without separator, so different prefixes can give the same result
+#[deprecated(note = "use a `Path` instead")]
 pub fn make_prefix(prefix: &str) -> Vec<u8> {
 	let e = prefix.split('.');
 	e
@@ -9,39 +12,37 @@ pub fn make_prefix(prefix: &str) -> Vec<u8> {
 		.collect::<Vec<u8>>()
 }
 
+/// Gives the bytes of a [Read], buffering it
 pub struct ReaderIter<R> {
-	reader: R,
+	reader: BufReader<R>,
 }
 
 impl<R: Read> ReaderIter<R> {
 	pub fn new(reader: R) -> Self {
-		Self { reader }
+		Self {
+			reader: BufReader::new(reader),
+		}
 	}
 
 	pub fn next_char(&mut self) -> Result<u8, Error> {
-		let mut buf = [0_u8; 1];
-		self.reader.read_exact(&mut buf)?;
-		Ok(buf[0])
+		let c = match self.reader.fill_buf()?.first() {
+			Some(c) => *c,
+			None => return Err(ErrorKind::UnexpectedEof.into()),
+		};
+		self.reader.consume(1);
+		Ok(c)
 	}
 }
 
+/// Ends at the end of the reader
 impl<R: Read> Iterator for ReaderIter<R> {
 	type Item = Result<u8, Error>;
 	fn next(&mut self) -> Option<Self::Item> {
-		Some(self.next_char())
+		let c = match self.reader.fill_buf() {
+			Ok(bytes) => *bytes.first()?,
+			Err(e) => return Some(Err(e)),
+		};
+		self.reader.consume(1);
+		Some(Ok(c))
 	}
 }
-
-
-pub fn compare_stack(stack: &[Vec<u8>], prefix: &[u8]) -> bool {
-	stack
-		.iter()
-		.flatten()
-		.zip(prefix.iter())
-		.take_while(|(a, b)| a == b)
-		.count() == prefix.len()
-}
-
-pub fn compare_stack_reader(stack: &[Vec<u8>], prefix: &[u8]) -> bool {
-	stack.join(".".as_bytes()) == prefix
-}
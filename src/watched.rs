@@ -0,0 +1,309 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Read};
+
+use serde::de::{self, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+
+use crate::scanner::is_scalar_byte;
+
+/// What serde was given of a value, and how far it had read on its first error
+#[derive(Default)]
+pub(crate) struct Given {
+	/// The number of bytes given
+	pub(crate) read: usize,
+	/// The number of bytes before each line but the first one
+	pub(crate) lines: Vec<usize>,
+	last: u8,
+	/// The number of bytes read on the first error, without the one serde only peeked then
+	pub(crate) stopped: Option<usize>,
+	/// If serde went into the array, object or enum being deserialized
+	entered: bool,
+	/// If serde is deserializing the key of an object
+	key: bool,
+}
+
+impl Given {
+	/// Called before serde deserializes a value, giving whether the one around it was entered
+	fn start(&mut self) -> bool {
+		self.stopped = None;
+		std::mem::take(&mut self.entered)
+	}
+
+	/// Called after serde deserialized a value, keeping where the first error stopped,
+	/// the values around it failing afterwards
+	fn stop<T, E>(&mut self, value: &Result<T, E>, outer: bool, number: bool) {
+		match value {
+			Ok(_) => self.stopped = None,
+			Err(_) if self.stopped.is_none() => {
+				// a value failing before it was entered was peeked at, unless it was a literal or a string,
+				// an array or an object failing once it was read up to its end,
+				// the quote ending a number in a key being peeked too
+				let peeked = match self.last {
+					_ if self.entered || self.read == 0 => false,
+					b'"' => self.key && number,
+					last => !is_scalar_byte(last),
+				};
+				self.stopped = Some(self.read - usize::from(peeked));
+			}
+			Err(_) => {}
+		}
+		self.entered = outer;
+	}
+}
+
+/// A [Read] counting the lines of the bytes given to serde
+pub(crate) struct Counting<'g, R> {
+	pub(crate) reader: R,
+	pub(crate) given: &'g RefCell<Given>,
+}
+
+impl<R: Read> Read for Counting<'_, R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let read = self.reader.read(buf)?;
+		let mut given = self.given.borrow_mut();
+		for newline in memchr::memchr_iter(b'\n', &buf[..read]) {
+			let start = given.read + newline + 1;
+			given.lines.push(start);
+		}
+		if let Some(last) = buf[..read].last() {
+			given.last = *last;
+		}
+		given.read += read;
+		Ok(read)
+	}
+}
+
+/// A deserializer, visitor, seed or access of serde, forwarding to the one it wraps
+/// while telling [Given] which values are deserialized, so that it knows where an error stopped
+pub(crate) struct Watched<'g, T> {
+	pub(crate) inner: T,
+	pub(crate) given: &'g RefCell<Given>,
+}
+
+impl<'g, T> Watched<'g, T> {
+	fn wrap<U>(&self, inner: U) -> Watched<'g, U> {
+		Watched {
+			inner,
+			given: self.given,
+		}
+	}
+}
+
+macro_rules! watch_deserialize {
+	($number:literal; $($method:ident($($arg:ident: $ty:ty),*);)*) => {$(
+		fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error> {
+			let visitor = self.wrap(visitor);
+			let outer = self.given.borrow_mut().start();
+			let value = self.inner.$method($($arg,)* visitor);
+			self.given.borrow_mut().stop(&value, outer, $number);
+			value
+		}
+	)*};
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for Watched<'_, D> {
+	type Error = D::Error;
+
+	watch_deserialize! {
+		true;
+		deserialize_i8();
+		deserialize_i16();
+		deserialize_i32();
+		deserialize_i64();
+		deserialize_i128();
+		deserialize_u8();
+		deserialize_u16();
+		deserialize_u32();
+		deserialize_u64();
+		deserialize_u128();
+		deserialize_f32();
+		deserialize_f64();
+	}
+
+	watch_deserialize! {
+		false;
+		deserialize_any();
+		deserialize_bool();
+		deserialize_char();
+		deserialize_str();
+		deserialize_string();
+		deserialize_bytes();
+		deserialize_byte_buf();
+		deserialize_option();
+		deserialize_unit();
+		deserialize_unit_struct(name: &'static str);
+		deserialize_newtype_struct(name: &'static str);
+		deserialize_seq();
+		deserialize_tuple(len: usize);
+		deserialize_tuple_struct(name: &'static str, len: usize);
+		deserialize_map();
+		deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+		deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+		deserialize_identifier();
+		deserialize_ignored_any();
+	}
+
+	fn is_human_readable(&self) -> bool {
+		self.inner.is_human_readable()
+	}
+}
+
+macro_rules! forward_visit {
+	($($method:ident($ty:ty);)*) => {$(
+		fn $method<E: de::Error>(self, v: $ty) -> Result<Self::Value, E> {
+			self.inner.$method(v)
+		}
+	)*};
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for Watched<'_, V> {
+	type Value = V::Value;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		self.inner.expecting(formatter)
+	}
+
+	forward_visit! {
+		visit_bool(bool);
+		visit_i8(i8);
+		visit_i16(i16);
+		visit_i32(i32);
+		visit_i64(i64);
+		visit_i128(i128);
+		visit_u8(u8);
+		visit_u16(u16);
+		visit_u32(u32);
+		visit_u64(u64);
+		visit_u128(u128);
+		visit_f32(f32);
+		visit_f64(f64);
+		visit_char(char);
+		visit_str(&str);
+		visit_borrowed_str(&'de str);
+		visit_string(String);
+		visit_bytes(&[u8]);
+		visit_borrowed_bytes(&'de [u8]);
+		visit_byte_buf(Vec<u8>);
+	}
+
+	fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+		self.inner.visit_none()
+	}
+
+	fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+		let deserializer = self.wrap(deserializer);
+		self.inner.visit_some(deserializer)
+	}
+
+	fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+		self.inner.visit_unit()
+	}
+
+	fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+		let deserializer = self.wrap(deserializer);
+		self.inner.visit_newtype_struct(deserializer)
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+		self.given.borrow_mut().entered = true;
+		let seq = self.wrap(seq);
+		self.inner.visit_seq(seq)
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+		self.given.borrow_mut().entered = true;
+		let map = self.wrap(map);
+		self.inner.visit_map(map)
+	}
+
+	fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+		self.given.borrow_mut().entered = true;
+		let data = self.wrap(data);
+		self.inner.visit_enum(data)
+	}
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Watched<'_, S> {
+	type Value = S::Value;
+
+	fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+		let deserializer = self.wrap(deserializer);
+		self.inner.deserialize(deserializer)
+	}
+}
+
+impl<'de, A: SeqAccess<'de>> SeqAccess<'de> for Watched<'_, A> {
+	type Error = A::Error;
+
+	fn next_element_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>, Self::Error> {
+		let seed = self.wrap(seed);
+		self.inner.next_element_seed(seed)
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		self.inner.size_hint()
+	}
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for Watched<'_, A> {
+	type Error = A::Error;
+
+	fn next_key_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>, Self::Error> {
+		let seed = self.wrap(seed);
+		let outer = std::mem::replace(&mut self.given.borrow_mut().key, true);
+		let key = self.inner.next_key_seed(seed);
+		self.given.borrow_mut().key = outer;
+		key
+	}
+
+	fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, Self::Error> {
+		let seed = self.wrap(seed);
+		self.inner.next_value_seed(seed)
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		self.inner.size_hint()
+	}
+}
+
+impl<'de, 'g, A: EnumAccess<'de>> EnumAccess<'de> for Watched<'g, A> {
+	type Error = A::Error;
+	type Variant = Watched<'g, A::Variant>;
+
+	fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Self::Variant), Self::Error> {
+		let seed = self.wrap(seed);
+		let (value, variant) = self.inner.variant_seed(seed)?;
+		let variant = Watched {
+			inner: variant,
+			given: self.given,
+		};
+		Ok((value, variant))
+	}
+}
+
+impl<'de, A: VariantAccess<'de>> VariantAccess<'de> for Watched<'_, A> {
+	type Error = A::Error;
+
+	fn unit_variant(self) -> Result<(), Self::Error> {
+		self.inner.unit_variant()
+	}
+
+	fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, Self::Error> {
+		let seed = self.wrap(seed);
+		self.inner.newtype_variant_seed(seed)
+	}
+
+	fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+		let visitor = self.wrap(visitor);
+		self.inner.tuple_variant(len, visitor)
+	}
+
+	fn struct_variant<V: Visitor<'de>>(
+		self,
+		fields: &'static [&'static str],
+		visitor: V,
+	) -> Result<V::Value, Self::Error> {
+		let visitor = self.wrap(visitor);
+		self.inner.struct_variant(fields, visitor)
+	}
+}
@@ -1,12 +1,17 @@
 #[cfg(test)]
 mod tests {
+	use std::error::Error;
 	use std::io::Read;
 	use std::{fs::File, io::BufReader};
 
 	use std::sync::Once;
 
 	static INIT: Once = Once::new();
-	use jsonit::{stream_read_items_at, JsonSeqIterator, ReaderIter};
+	use jsonit::{
+		read_value_at, stream_raw_items_at, stream_read_items_at, try_stream_read_items_at, ErrorKind, JsonKvIterator,
+		JsonMultiIterator, JsonSeqIterator, JsonSliceIterator, Location, Path, RawValue, ReaderIter, Segment, Slice,
+		ValueKind,
+	};
 	use serde::de::DeserializeOwned;
 	use serde::Deserialize;
 
@@ -30,6 +35,10 @@ mod tests {
 	build_on!("test.json");
 	build_on!("simple.json");
 	build_on!("test_confuse.json");
+	build_on!("root_array.json");
+	build_on!("pages.json");
+	build_on!("multi.json");
+	build_on!("test_nasty.json");
 
 	fn init_logging(level: log::LevelFilter) -> Result<(), fern::InitError> {
 		let colors = fern::colors::ColoredLevelConfig::default().info(fern::colors::Color::Blue);
@@ -83,6 +92,44 @@ mod tests {
 		assert!(count == 2);
 	}
 
+	#[test]
+	fn test_root_array() {
+		let f = File::open("./tests/root_array.json").expect("failed to read test file");
+		let chars = ReaderIter::new(BufReader::new(f)).map(|e| e.expect("failed to read file"));
+		let names = stream_read_items_at::<Value>(chars, "")
+			.map(|e| e.expect("failed to parse item").name)
+			.collect::<Vec<_>>();
+		assert_eq!(names, ["hello1", "hello2"]);
+	}
+
+	fn read_names_at(path: &str, prefix: &str) -> Vec<String> {
+		let f = File::open(path).expect("failed to read test file");
+		let chars = ReaderIter::new(BufReader::new(f)).map(|e| e.expect("failed to read file"));
+		stream_read_items_at::<Value>(chars, prefix)
+			.map(|e| e.expect("failed to parse item").name)
+			.collect()
+	}
+
+	#[test]
+	fn test_index_and_wildcard() {
+		assert_eq!(
+			read_names_at("./tests/pages.json", "pages.0.items"),
+			["hello1", "hello2"]
+		);
+		assert_eq!(read_names_at("./tests/pages.json", "pages.2.items"), ["hello3"]);
+		assert!(read_names_at("./tests/pages.json", "pages.1.items").is_empty());
+		assert_eq!(
+			read_names_at("./tests/pages.json", "pages.*.items"),
+			["hello1", "hello2", "hello3"]
+		);
+		let f = File::open("./tests/root_array.json").expect("failed to read test file");
+		let chars = ReaderIter::new(BufReader::new(f)).map(|e| e.expect("failed to read file"));
+		let ops = stream_read_items_at::<Op>(chars, "*.op")
+			.map(|e| e.expect("failed to parse item").a)
+			.collect::<Vec<_>>();
+		assert_eq!(ops, ["a", "b"]);
+	}
+
 	#[test]
 	fn test_nominal_array() {
 		let prefix = "array";
@@ -222,6 +269,1288 @@ mod tests {
 		BufReader::new(f)
 	}
 
+	#[test]
+	fn reader_root_array() -> TestResult {
+		for prefix in ["", "$"] {
+			let iterator = JsonSeqIterator::new(get_test_local_reader("./tests/root_array.json"), prefix.as_bytes());
+			let values = iterator.collect::<Result<Vec<Value>, _>>()?;
+			assert_eq!(values.len(), 2);
+			assert_eq!(values[1].name, "hello2");
+			assert_eq!(values[1].op[0].a, "b");
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn reader_root_array_scalars() -> TestResult {
+		let iterator = JsonSeqIterator::new(r#" [1, 2,null ,3]"#.as_bytes(), b"$");
+		let values = iterator.collect::<Result<Vec<Option<u32>>, _>>()?;
+		assert_eq!(values, [Some(1), Some(2), None, Some(3)]);
+
+		let iterator = JsonSeqIterator::new("[]".as_bytes(), b"");
+		assert_eq!(iterator.collect::<Result<Vec<u32>, _>>()?.len(), 0);
+		Ok(())
+	}
+
+	fn reader_names_at(path: &str, prefix: &str) -> Result<Vec<String>, JsonItError> {
+		let iterator = JsonSeqIterator::new(get_test_local_reader(path), prefix.as_bytes());
+		iterator.map(|e| e.map(|v: Value| v.name)).collect()
+	}
+
+	#[test]
+	fn reader_index_and_wildcard() -> TestResult {
+		assert_eq!(
+			reader_names_at("./tests/pages.json", "pages.0.items")?,
+			["hello1", "hello2"]
+		);
+		assert_eq!(reader_names_at("./tests/pages.json", "pages.2.items")?, ["hello3"]);
+		assert!(reader_names_at("./tests/pages.json", "pages.1.items")?.is_empty());
+		assert_eq!(
+			reader_names_at("./tests/pages.json", "pages.*.items")?,
+			["hello1", "hello2", "hello3"]
+		);
+
+		let iterator = JsonSeqIterator::new(r#"{"a": [[1, 2], [], [3]], "b": 0}"#.as_bytes(), b"a.*");
+		assert_eq!(iterator.collect::<Result<Vec<u32>, _>>()?, [1, 2, 3]);
+
+		let iterator = JsonSeqIterator::new(r#"[[1], [2, 3]]"#.as_bytes(), b"1");
+		assert_eq!(iterator.collect::<Result<Vec<u32>, _>>()?, [2, 3]);
+		Ok(())
+	}
+
+	#[test]
+	fn path_parse_and_build() {
+		let path: Path = "pages.0.*.items".parse().unwrap();
+		assert_eq!(path, Path::root().key("pages").index(0).wildcard().key("items"));
+		assert_eq!(
+			path.segments(),
+			[
+				Segment::Key("pages".to_owned()),
+				Segment::Index(0),
+				Segment::Wildcard,
+				Segment::Key("items".to_owned())
+			]
+		);
+		assert_eq!(Path::parse("").unwrap(), Path::root());
+		assert_eq!(Path::parse("$").unwrap(), Path::root());
+		assert_eq!(
+			Path::parse(r#"by_id."123"."a.b""#).unwrap(),
+			Path::root().key("by_id").key("123").key("a.b")
+		);
+		assert_ne!(Path::parse("ab.c").unwrap(), Path::parse("a.bc").unwrap());
+		assert!(Path::parse(r#""unterminated.a"#).is_err());
+		assert!(Path::parse(r#""a"b"#).is_err());
+		for (path, position) in [("a..b", 2), ("a.", 2), (".a", 0)] {
+			let error = Path::parse(path).unwrap_err();
+			assert_eq!(
+				(error.position, error.reason),
+				(position, "empty key, which must be quoted")
+			);
+		}
+		assert_eq!(
+			Path::parse(r#"a."".b"#).unwrap(),
+			Path::root().key("a").key("").key("b")
+		);
+	}
+
+	#[test]
+	fn path_display_round_trip() {
+		let paths = [
+			Path::root(),
+			Path::root().key("pages").index(0).wildcard().key("items"),
+			Path::root()
+				.key("a.b")
+				.key("")
+				.key("*")
+				.key("12")
+				.key("$x")
+				.key("/y")
+				.key("q\"uote"),
+			Path::root().key(""),
+		];
+		for path in paths {
+			let displayed = path.to_string();
+			assert_eq!(Path::parse(&displayed).unwrap(), path, "{}", displayed);
+		}
+		assert_eq!(Path::parse("root.items").unwrap().to_string(), "root.items");
+	}
+
+	#[test]
+	fn path_json_pointer() {
+		assert_eq!(
+			Path::parse("/data/items").unwrap(),
+			Path::root().key("data").key("items")
+		);
+		assert_eq!(
+			Path::parse("/a~1b/~0c/0/01/").unwrap(),
+			Path::root().key("a/b").key("~c").index(0).key("01").key("")
+		);
+		assert_eq!(Path::from_pointer("").unwrap(), Path::root());
+		assert_eq!(Path::from_pointer("/").unwrap(), Path::root().key(""));
+		assert!(Path::from_pointer("/a~2").is_err());
+		assert!(Path::from_pointer("/a~").is_err());
+		assert!(Path::from_pointer("a").is_err());
+
+		let path = Path::root().key("a/b").key("~c").index(3).key("*");
+		assert_eq!(path.to_pointer().unwrap(), "/a~1b/~0c/3/*");
+		assert_eq!(Path::from_pointer(&path.to_pointer().unwrap()).unwrap(), path);
+		assert!(Path::root().wildcard().to_pointer().is_none());
+	}
+
+	#[test]
+	fn reader_json_pointer() -> TestResult {
+		assert_eq!(reader_names_at("./tests/pages.json", "/pages/2/items")?, ["hello3"]);
+		assert_eq!(
+			reader_names_at("./tests/test.json", "/root/items")?,
+			["hello1", "hello2"]
+		);
+
+		// a numeric token designates either an array element or an object member
+		let data = r#"{"by_id": {"12": {"a/b": [1, 2]}}, "list": [[], {"a/b": [3]}]}"#;
+		let iterator = JsonSeqIterator::new(data.as_bytes(), "/by_id/12/a~1b");
+		assert_eq!(iterator.collect::<Result<Vec<u32>, _>>()?, [1, 2]);
+		let iterator = JsonSeqIterator::new(data.as_bytes(), "/list/1/a~1b");
+		assert_eq!(iterator.collect::<Result<Vec<u32>, _>>()?, [3]);
+
+		assert_eq!(
+			read_names_at("./tests/pages.json", "/pages/0/items"),
+			["hello1", "hello2"]
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn path_jsonpath() {
+		assert_eq!(Path::parse("$.a.b[*]").unwrap(), Path::parse("a.b").unwrap());
+		assert_eq!(Path::parse("$.a.b.*").unwrap(), Path::parse("a.b").unwrap());
+		assert_eq!(Path::parse("$.a.b").unwrap(), Path::parse("a.b").unwrap());
+		assert_eq!(
+			Path::parse("$..items[*]").unwrap(),
+			Path::root().descendants().key("items")
+		);
+		assert_eq!(
+			Path::parse("$.a[2:10]").unwrap(),
+			Path::root().key("a").with_items(2..10)
+		);
+		assert_eq!(Path::parse("$.a[3]").unwrap(), Path::root().key("a").with_items(3..4));
+		assert_eq!(
+			Path::parse("$['a.b'][1:][*]..[\"c\"][::2]").unwrap(),
+			Path::root()
+				.key("a.b")
+				.slice(1..)
+				.wildcard()
+				.descendants()
+				.key("c")
+				.with_items(Slice::from(0..).with_step(2))
+		);
+		assert!(Path::parse("$.a[-1]").is_err());
+		assert!(Path::parse("$.a[1:-1]").is_err());
+		assert!(Path::parse("$.a[::0]").is_err());
+		assert!(Path::parse("$.a[").is_err());
+		assert!(Path::parse("$.a['b").is_err());
+		assert!(Path::parse("$a").is_err());
+
+		let paths = [
+			Path::root().descendants().key("items"),
+			Path::root().key("a").with_items(3..4),
+			Path::root()
+				.key("a b")
+				.index(2)
+				.slice(Slice::from(1..7).with_step(3))
+				.wildcard(),
+			Path::root().descendants(),
+			Path::root().key("q'uote\\").descendants().index(0).with_items(2..),
+			Path::root().with_items(0..2),
+		];
+		for path in paths {
+			let displayed = path.to_string();
+			assert_eq!(Path::parse(&displayed).unwrap(), path, "{}", displayed);
+		}
+	}
+
+	#[test]
+	fn reader_jsonpath() -> TestResult {
+		assert_eq!(
+			reader_names_at("./tests/pages.json", "$.pages[*].items[*]")?,
+			["hello1", "hello2", "hello3"]
+		);
+		assert_eq!(
+			reader_names_at("./tests/pages.json", "$..items[*]")?,
+			["hello1", "hello2", "hello3"]
+		);
+		assert_eq!(reader_names_at("./tests/pages.json", "$.pages[1:].items")?, ["hello3"]);
+		assert_eq!(
+			reader_names_at("./tests/pages.json", "$.pages[0].items[1:]")?,
+			["hello2"]
+		);
+		assert_eq!(read_names_at("./tests/pages.json", "$..items[1]"), ["hello2"]);
+
+		let data = r#"{"a": [0, 1, 2, 3, 4, 5, 6], "b": {"a": [[7, 8], [9]]}}"#;
+		let read = |path: &str| JsonSeqIterator::new(data.as_bytes(), path).collect::<Result<Vec<u32>, _>>();
+		assert_eq!(read("$.a[2:5]")?, [2, 3, 4]);
+		assert_eq!(read("$.a[1::2]")?, [1, 3, 5]);
+		assert_eq!(read("$.a[4]")?, [4]);
+		assert_eq!(read("$.b.a[*][0]")?, [7, 9]);
+		assert_eq!(read("$..a[*][1:]")?, [8]);
+		// stops reading once the last needed item is read
+		let truncated = r#"{"a": [0, 1, 2, "#;
+		let iterator = JsonSeqIterator::new(truncated.as_bytes(), "$.a[:2]");
+		assert_eq!(iterator.collect::<Result<Vec<u32>, _>>()?, [0, 1]);
+		Ok(())
+	}
+
+	#[test]
+	fn reader_path_with_dots() -> TestResult {
+		let data = r#"{"ab": {"c": [[1]]}, "a": {"bc": [2]}, "a.b": {"c": [3]}}"#;
+		let path = Path::root().key("a").key("bc");
+		let iterator = JsonSeqIterator::new(data.as_bytes(), &path);
+		assert_eq!(iterator.collect::<Result<Vec<u32>, _>>()?, [2]);
+		let iterator = JsonSeqIterator::new(data.as_bytes(), r#""a.b".c"#);
+		assert_eq!(iterator.collect::<Result<Vec<u32>, _>>()?, [3]);
+
+		let chars = data.bytes();
+		let items = stream_read_items_at::<Vec<u32>>(chars, Path::root().key("ab").key("c"));
+		assert_eq!(items.map(|e| e.unwrap()).collect::<Vec<_>>(), [[1]]);
+		Ok(())
+	}
+
+	#[test]
+	fn reader_invalid_path() {
+		let mut iterator = JsonSeqIterator::<_, u32>::new("[1]".as_bytes(), r#""a"#);
+		assert!(matches!(iterator.next(), Some(Err(JsonItError::InvalidPath(_)))));
+		assert!(iterator.next().is_none());
+		let mut items = stream_read_items_at::<u32>("[1]".bytes(), r#""a"#);
+		assert!(items.next().unwrap().is_err());
+		assert!(items.next().is_none());
+	}
+
+	#[test]
+	fn reader_path_not_found() -> TestResult {
+		let data = r#"{"a": [1], "b": {"c": 2}, "d": null, "e": [{"f": [3]}, {"f": 4}]}"#;
+		let mut iterator = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "x");
+		assert!(matches!(
+			iterator.next(),
+			Some(Err(JsonItError::PathNotFound { path, .. })) if path == Path::root().key("x")
+		));
+		assert!(iterator.next().is_none());
+		// also when the document is truncated
+		let mut iterator = JsonSeqIterator::<_, u32>::new(r#"{"a": [1], "b": "#.as_bytes(), "x");
+		assert!(matches!(iterator.next(), Some(Err(JsonItError::PathNotFound { .. }))));
+
+		let mut iterator = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "x").missing_as_empty();
+		assert!(iterator.next().is_none());
+		let mut iterator = JsonKvIterator::<_, u32>::new(data.as_bytes(), "x").missing_as_empty();
+		assert!(iterator.next().is_none());
+
+		// an empty array is not a missing one
+		assert_eq!(
+			JsonSeqIterator::<_, u32>::new(r#"{"a": []}"#.as_bytes(), "a").count(),
+			0
+		);
+
+		let mut iterator = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "d");
+		assert!(matches!(
+			iterator.next(),
+			Some(Err(JsonItError::NotAnArray {
+				found: ValueKind::Null,
+				..
+			}))
+		));
+		let mut iterator = JsonKvIterator::<_, u32>::new(data.as_bytes(), "a");
+		assert!(matches!(
+			iterator.next(),
+			Some(Err(JsonItError::NotAnObject {
+				found: ValueKind::Array,
+				..
+			}))
+		));
+
+		// with wildcards, the values of other kinds are skipped
+		let items = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "e.*.f").collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(items, [3]);
+		let mut iterator = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "*.y");
+		assert!(matches!(iterator.next(), Some(Err(JsonItError::PathNotFound { .. }))));
+
+		// every path missing from the document is reported
+		let mut iterator = JsonMultiIterator::new(data.as_bytes())
+			.with("a", Record::Order)
+			.with("x", Record::Order);
+		assert_eq!(iterator.next().transpose()?, Some(Record::Order(1)));
+		assert!(matches!(
+			iterator.next(),
+			Some(Err(JsonItError::PathNotFound { path, .. })) if path.to_string() == "x"
+		));
+		let iterator = JsonMultiIterator::new(data.as_bytes())
+			.with("a", Record::Order)
+			.with("x", Record::Order)
+			.missing_as_empty();
+		assert_eq!(iterator.collect::<Result<Vec<_>, _>>()?, [Record::Order(1)]);
+
+		let mut items = stream_read_items_at::<u32>(data.bytes(), "x");
+		assert!(matches!(items.next(), Some(Err(JsonItError::PathNotFound { .. }))));
+		assert!(items.next().is_none());
+		let mut items = stream_read_items_at::<u32>(data.bytes(), "b");
+		assert!(matches!(
+			items.next(),
+			Some(Err(JsonItError::NotAnArray {
+				found: ValueKind::Object,
+				..
+			}))
+		));
+		assert!(items.next().is_none());
+
+		// the rest of the document is not read once a single value designated is not an array
+		let data = r#"{"a": 5, "b": x}"#;
+		let messages = |items: Vec<Result<u32, JsonItError>>| {
+			items
+				.into_iter()
+				.map(|item| item.map_err(|e| e.to_string()))
+				.collect::<Vec<_>>()
+		};
+		let read = messages(JsonSeqIterator::new(data.as_bytes(), "a").collect());
+		assert!(matches!(read[..], [Err(_)]), "{:?}", read);
+		let mut iterator = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "a");
+		assert!(matches!(iterator.next(), Some(Err(JsonItError::NotAnArray { .. }))));
+		assert_eq!(read, messages(stream_read_items_at(data.bytes(), "a").collect()));
+		assert_eq!(read, messages(JsonSliceIterator::new(data, "a").collect()));
+		Ok(())
+	}
+
+	#[test]
+	fn malformed_documents() {
+		for data in [
+			r#"{"b" 1, "a": [[1]]}"#,
+			r#"{"b": 1 "a": [[1]]}"#,
+			r#"}"#,
+			r#"{"a": [[1]] x"#,
+		] {
+			let mut iterator = JsonSeqIterator::<_, Vec<u32>>::new(data.as_bytes(), "c");
+			assert!(
+				matches!(iterator.next(), Some(Err(JsonItError::InvalidJsonCharacter { .. }))),
+				"{}",
+				data
+			);
+			assert!(iterator.next().is_none());
+			let mut items = stream_read_items_at::<Vec<u32>>(data.bytes(), "c");
+			assert!(
+				matches!(items.next(), Some(Err(JsonItError::InvalidJsonCharacter { .. }))),
+				"{}",
+				data
+			);
+			assert!(items.next().is_none());
+		}
+
+		// truncated in the array
+		let data = r#"{"a": [[1], [2"#;
+		let mut iterator = JsonSeqIterator::<_, Vec<u32>>::new(data.as_bytes(), "a");
+		assert_eq!(iterator.next().transpose().ok(), Some(Some(vec![1])));
+		assert!(iterator.next().unwrap().is_err());
+		assert!(iterator.next().is_none());
+		let mut items = stream_read_items_at::<Vec<u32>>(data.bytes(), "a");
+		assert_eq!(items.next().transpose().ok(), Some(Some(vec![1])));
+		assert!(matches!(items.next(), Some(Err(JsonItError::IoError { .. }))));
+		assert!(items.next().is_none());
+
+		// a syntax error in an item ends both iterators
+		for data in [r#"[tru, 1]"#, r#"[{"a" 1}, 2]"#] {
+			let items = JsonSeqIterator::<_, serde_json::Value>::new(data.as_bytes(), "").collect::<Vec<_>>();
+			assert!(matches!(items[..], [Err(JsonItError::SerdeError { .. })]), "{}", data);
+			let items = stream_read_items_at::<serde_json::Value>(data.bytes(), "").collect::<Vec<_>>();
+			assert!(matches!(items[..], [Err(JsonItError::SerdeError { .. })]), "{}", data);
+		}
+
+		// a separator is needed between items, and only there
+		for (data, items) in [
+			("[1 2]", 1),
+			("[1,,2]", 1),
+			("[1,2,]", 2),
+			("[{} x {}]", 1),
+			("[,1]", 0),
+			// the scalar before the wrong byte is given first
+			("[1}", 1),
+			("[1, 2}", 2),
+		] {
+			let read = JsonSeqIterator::<_, serde_json::Value>::new(data.as_bytes(), "").collect::<Vec<_>>();
+			let streamed = stream_read_items_at::<serde_json::Value>(data.bytes(), "").collect::<Vec<_>>();
+			let sliced = JsonSliceIterator::<serde_json::Value>::new(data, "").collect::<Vec<_>>();
+			for results in [&read, &streamed, &sliced] {
+				assert_eq!(results.len(), items + 1, "{}", data);
+				assert!(results[..items].iter().all(Result::is_ok), "{}", data);
+				assert!(
+					matches!(results[items], Err(JsonItError::InvalidJsonCharacter { .. })),
+					"{}",
+					data
+				);
+			}
+			assert_eq!(
+				read[items].as_ref().err().and_then(|e| e.location()),
+				streamed[items].as_ref().err().and_then(|e| e.location())
+			);
+			assert_eq!(format!("{:?}", read), format!("{:?}", sliced), "{}", data);
+		}
+
+		// unexpected character between items
+		let mut iterator = JsonSeqIterator::<_, u32>::new(r#"{"a": [1, :]}"#.as_bytes(), "a");
+		assert_eq!(iterator.next().transpose().ok(), Some(Some(1)));
+		assert!(iterator.next().unwrap().is_err());
+		assert!(iterator.next().is_none());
+	}
+
+	#[test]
+	fn error_locations() {
+		let data = "{\n\t\"meta\": {\"x\": [1]},\n\t\"items\": [\n\t\t{\"name\": \"a\", \"op\": []},\n\t\t{\"name\": 1, \"op\": []},\n\t\t{\"name\": \"c\", \"op\": []}\n\t]\n}";
+		let items = JsonSeqIterator::<_, Value>::new(data.as_bytes(), "items").collect::<Vec<_>>();
+		assert_eq!(items.len(), 3, "the iterator goes on after the wrong item");
+		let location = items[1].as_ref().err().and_then(|e| e.location()).cloned().unwrap();
+		assert_eq!((location.line, location.item), (5, Some(1)));
+		assert_eq!(location.path, Path::root().key("items"));
+		assert_eq!(items[2].as_ref().map(|v| v.name.as_str()).ok(), Some("c"));
+
+		let items = stream_read_items_at::<Value>(data.bytes(), "items");
+		let errors = items.filter_map(Result::err).collect::<Vec<_>>();
+		assert_eq!(errors.len(), 1);
+		let location = errors[0].location().unwrap();
+		assert_eq!((location.line, location.item), (5, Some(1)));
+
+		// the three ways of streaming locate an error where serde stopped in the item
+		let data = "{\"x\": [{\"name\": \"a\", \"op\": []},\n\t{\"name\": true, \"op\": []}, {\"name\": 01}]}";
+		let expected = [(46, 2, 14, Some(1)), (70, 2, 38, Some(2))];
+		let locations = |items: Vec<Result<Value, JsonItError>>| {
+			items
+				.iter()
+				.filter_map(|item| item.as_ref().err()?.location())
+				.map(|l| (l.offset, l.line, l.column, l.item))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(
+			locations(JsonSeqIterator::new(data.as_bytes(), "x").collect()),
+			expected
+		);
+		assert_eq!(locations(stream_read_items_at(data.bytes(), "x").collect()), expected);
+		assert_eq!(locations(JsonSliceIterator::new(data, "x").collect()), expected);
+
+		// the byte serde peeked after a number or at a value of the wrong type is left out,
+		// an object failing at its end
+		#[derive(Deserialize, Debug)]
+		struct Inner {
+			#[allow(dead_code)]
+			a: u32,
+		}
+		#[derive(Deserialize, Debug)]
+		struct Outer {
+			#[allow(dead_code)]
+			a: Inner,
+		}
+		fn positions<T: DeserializeOwned>(data: &str) -> [Vec<(u64, u64)>; 3] {
+			let located = |items: Vec<Result<T, JsonItError>>| {
+				items
+					.iter()
+					.filter_map(|item| item.as_ref().err()?.location())
+					.map(|l| (l.line, l.column))
+					.collect()
+			};
+			[
+				located(JsonSeqIterator::new(data.as_bytes(), "").collect()),
+				located(stream_read_items_at(data.bytes(), "").collect()),
+				located(JsonSliceIterator::new(data, "").collect()),
+			]
+		}
+		let same = |positions: Vec<(u64, u64)>| [positions.clone(), positions.clone(), positions];
+		assert_eq!(positions::<u32>("[1, 1.5, -3]"), same(vec![(1, 7), (1, 11)]));
+		assert_eq!(positions::<Inner>("[{\"a\": 1.5}]"), same(vec![(1, 10)]));
+		assert_eq!(positions::<Inner>("[{\"a\": {\"q\": 1}}]"), same(vec![(1, 7)]));
+		assert_eq!(positions::<Inner>("[{\"a\": [1] }]"), same(vec![(1, 7)]));
+		assert_eq!(positions::<Inner>("[{\"b\": 1}]"), same(vec![(1, 9)]));
+		assert_eq!(positions::<Inner>("[{\"a\":\n 1.5\n}]"), same(vec![(2, 4)]));
+		assert_eq!(positions::<Outer>("[{\"a\": {\"a\": 1.5}}]"), same(vec![(1, 16)]));
+
+		let data = "{\"a\": 1,\n \"b\" x}";
+		let expected = Location {
+			offset: 15,
+			line: 2,
+			column: 6,
+			item: None,
+			path: Path::root().key("b"),
+		};
+		let error = JsonSeqIterator::<_, Value>::new(data.as_bytes(), "c")
+			.next()
+			.unwrap()
+			.err()
+			.unwrap();
+		assert_eq!(error.location(), Some(&expected));
+		let mut items = stream_read_items_at::<Value>(data.bytes(), "c");
+		let error = items.next().unwrap().err().unwrap();
+		assert_eq!(error.location(), Some(&expected));
+		assert_eq!(expected.to_string(), "line 2, column 6 (byte 15) in b");
+	}
+
+	#[test]
+	fn error_display_and_kind() -> Result<(), Box<dyn Error>> {
+		let data = "{\"a\": 1,\n \"b\" x}";
+		let error = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "c")
+			.next()
+			.unwrap()
+			.unwrap_err();
+		assert_eq!(
+			error.to_string(),
+			"[JsonIt] Unexpected character: 'x' at line 2, column 6 (byte 15) in b"
+		);
+		assert_eq!(error.kind(), ErrorKind::Syntax);
+		assert!(error.source().is_none());
+
+		let error = read_value_at::<u32>(r#"{"a": "1"}"#.as_bytes(), "a").unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::Data);
+		assert!(error.source().unwrap().is::<serde_json::Error>());
+
+		let error = read_value_at::<u32>(r#"{"a": "1"}"#.as_bytes(), "b").unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::Path);
+		assert_eq!(
+			error.to_string(),
+			"[JsonIt] Path not found: b at line 1, column 10 (byte 10) in $"
+		);
+		let error = read_value_at::<u32>(r#"{"a": "1"}"#.as_bytes(), "$[").unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::Path);
+
+		let nested = format!("{}{}", "[".repeat(200), "]".repeat(200));
+		let error = read_value_at::<serde_json::Value>(nested.as_bytes(), "").unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::Limit);
+
+		let error = JsonItError::from(std::io::Error::other("disk failure"));
+		assert_eq!(error.kind(), ErrorKind::Io);
+		assert_eq!(error.to_string(), "[JsonIt] Could not read the document");
+		assert_eq!(error.source().unwrap().to_string(), "disk failure");
+
+		// can be used with ? into a boxed error
+		let value: u32 = read_value_at(r#"{"a": 1}"#.as_bytes(), "a")?;
+		assert_eq!(value, 1);
+		Ok(())
+	}
+
+	#[derive(Deserialize, Debug, PartialEq)]
+	struct Nasty {
+		name: String,
+		v: serde_json::Value,
+	}
+
+	#[test]
+	fn skip_nasty_siblings() -> TestResult {
+		let expected = ["a ] }", "b", "\\\""];
+		let iterator =
+			JsonSeqIterator::<_, Nasty>::new(get_test_local_reader("./tests/test_nasty.json"), "target.items");
+		let names = iterator
+			.map(|item| item.map(|item| item.name))
+			.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(names, expected);
+
+		let chars = std::fs::read("./tests/test_nasty.json")
+			.expect("failed to read test file")
+			.into_iter();
+		let items = stream_read_items_at::<Nasty>(chars, "target.items");
+		let names = items
+			.map(|item| item.map(|item| item.name))
+			.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(names, expected);
+
+		let path = Path::root().key("target.items");
+		let iterator = JsonSeqIterator::<_, u32>::new(get_test_local_reader("./tests/test_nasty.json"), &path);
+		assert_eq!(iterator.collect::<Result<Vec<_>, _>>()?, [99]);
+
+		let after =
+			read_value_at::<serde_json::Value>(get_test_local_reader("./tests/test_nasty.json"), "target.after")?;
+		assert_eq!(after, serde_json::json!([[[[]]]]));
+		let iterator =
+			JsonKvIterator::<_, serde_json::Value>::new(get_test_local_reader("./tests/test_nasty.json"), "obj");
+		let keys = iterator
+			.map(|entry| entry.map(|(key, _)| key))
+			.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(keys, ["items", "deep", "last"]);
+
+		// every kind of value as the last member of an object
+		for last in [
+			"true",
+			"false",
+			"null",
+			"-1.5e+10",
+			"0",
+			r#""}""#,
+			"[[]]",
+			r#"{"a": {}}"#,
+		] {
+			let data = format!(r#"{{"a": {{"b": {}}}, "c": [[1]]}}"#, last);
+			let iterator = JsonSeqIterator::<_, Vec<u32>>::new(data.as_bytes(), "c");
+			assert_eq!(iterator.collect::<Result<Vec<_>, _>>()?, [[1]], "{}", data);
+			let items = stream_read_items_at::<Vec<u32>>(data.bytes(), "c");
+			assert_eq!(items.collect::<Result<Vec<_>, _>>()?, [[1]], "{}", data);
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn escaped_keys() -> TestResult {
+		let data = r#"{"a\"b": [[1]], "caf\u00e9": [[2]], "\ud83d\ude00": [[3]], "back\\": [[4]], "\"target\"": [[5]], "x": {"\u0031": [[6]]}}"#;
+		for (path, expected) in [
+			(Path::root().key("a\"b"), 1),
+			(Path::root().key("café"), 2),
+			(Path::root().key("😀"), 3),
+			(Path::root().key("back\\"), 4),
+			(Path::root().key("\"target\""), 5),
+			(Path::root().key("x").index(1), 6),
+		] {
+			let iterator = JsonSeqIterator::<_, Vec<u32>>::new(data.as_bytes(), &path);
+			assert_eq!(iterator.collect::<Result<Vec<_>, _>>()?, [[expected]], "{}", path);
+			let items = stream_read_items_at::<Vec<u32>>(data.bytes(), &path);
+			assert_eq!(items.collect::<Result<Vec<_>, _>>()?, [[expected]], "{}", path);
+		}
+
+		let iterator =
+			JsonSeqIterator::<_, u32>::new(get_test_local_reader("./tests/test_nasty.json"), r#""\"target\"""#);
+		assert_eq!(iterator.collect::<Result<Vec<_>, _>>()?, [0]);
+
+		// the location gives the decoded key
+		let data = r#"{"caf\u00e9": [1, "x"]}"#;
+		let error = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "café")
+			.nth(1)
+			.unwrap()
+			.unwrap_err();
+		assert_eq!(error.location().unwrap().path, Path::root().key("café"));
+
+		// a lone surrogate is not a valid key
+		let data = r#"{"\ud83d": [1]}"#;
+		let mut iterator = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "a");
+		assert!(matches!(
+			iterator.next(),
+			Some(Err(JsonItError::InvalidJsonCharacter { .. }))
+		));
+		Ok(())
+	}
+
+	fn stream_values(data: &str, path: &str) -> Result<Vec<serde_json::Value>, JsonItError> {
+		stream_read_items_at(data.bytes(), path).collect()
+	}
+
+	#[test]
+	fn stream_borrowed_bytes() -> TestResult {
+		let data = String::from(r#"{"a": [1, 2, 3]}"#);
+		// a slice, an iterator of references and a vector, borrowed or not
+		let from_slice = stream_read_items_at::<u32>(data.as_bytes(), "a").collect::<Result<Vec<_>, _>>()?;
+		let from_refs = stream_read_items_at::<u32>(data.as_bytes().iter().filter(|c| **c != b' '), "a");
+		let bytes = data.clone().into_bytes();
+		let from_vec = stream_read_items_at::<u32>(&bytes, "a").collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(from_slice, [1, 2, 3]);
+		assert_eq!(from_refs.collect::<Result<Vec<_>, _>>()?, from_slice);
+		assert_eq!(from_vec, from_slice);
+		assert_eq!(stream_read_items_at::<u32>(bytes, "a").count(), 3);
+		Ok(())
+	}
+
+	/// A reader failing after giving some bytes
+	struct FailingReader<'a>(&'a [u8]);
+
+	impl Read for FailingReader<'_> {
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			match self.0.read(buf)? {
+				0 => Err(std::io::Error::other("disk failure")),
+				read => Ok(read),
+			}
+		}
+	}
+
+	#[test]
+	fn stream_fallible_bytes() -> TestResult {
+		assert_eq!(ReaderIter::new("ab".as_bytes()).collect::<Result<Vec<_>, _>>()?, b"ab");
+
+		let reader = ReaderIter::new(File::open("./tests/test.json")?);
+		let names = try_stream_read_items_at::<Value>(reader, "root.items")
+			.map(|item| item.map(|item| item.name))
+			.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(names, ["hello1", "hello2"]);
+
+		// a failed read, before the array or in an item, ends the iterator
+		for (data, expected) in [(r#"{"a": "#, 0), (r#"{"a": [1, [2, 3"#, 1)] {
+			let reader = ReaderIter::new(FailingReader(data.as_bytes()));
+			let items = try_stream_read_items_at::<serde_json::Value>(reader, "a").collect::<Vec<_>>();
+			assert_eq!(items.len(), expected + 1, "{:?}", items);
+			let error = items.last().unwrap().as_ref().unwrap_err();
+			assert_eq!(error.kind(), ErrorKind::Io);
+			assert_eq!(error.location().map(|l| l.offset), Some(data.len() as u64));
+			assert_eq!(error.source().map(|e| e.to_string()).as_deref(), Some("disk failure"));
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn raw_items() -> TestResult {
+		let data = r#"{"a": [ {"b" : [1, "]"]}, "x\"y",-1.5e3 , null, [ ] ]}"#;
+		let expected = [r#"{"b" : [1, "]"]}"#, r#""x\"y""#, "-1.5e3", "null", "[ ]"];
+
+		let items = JsonSeqIterator::raw(data.as_bytes(), "a").collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(items.iter().map(|item| item.get()).collect::<Vec<_>>(), expected);
+
+		let items = stream_raw_items_at(data.bytes(), "a").collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(items.iter().map(|item| item.get()).collect::<Vec<_>>(), expected);
+
+		// borrowed from the document in memory, without copy
+		let items = JsonSliceIterator::<&RawValue>::new(data, "a").collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(items.iter().map(|item| item.get()).collect::<Vec<_>>(), expected);
+
+		// invalid items are still reported
+		let items = JsonSeqIterator::raw(r#"[1, tru, 2]"#.as_bytes(), "").collect::<Vec<_>>();
+		assert!(items[0].is_ok());
+		assert!(items[1].is_err());
+		let items = stream_raw_items_at(r#"[{"a": 1,}, 2]"#.bytes(), "").collect::<Vec<_>>();
+		assert!(items[0].is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_scalar_items() -> TestResult {
+		let items = stream_read_items_at::<u64>(r#"{"ids":[1,2,3]}"#.bytes(), "ids");
+		assert_eq!(items.collect::<Result<Vec<_>, _>>()?, [1, 2, 3]);
+
+		let data = r#"{"a": [ "x]\"y" , -1.5e3,true,false , null, 0, {"b": "}"}, [2], "" ]}"#;
+		let expected = serde_json::json!(["x]\"y", -1.5e3, true, false, null, 0, {"b": "}"}, [2], ""]);
+		assert_eq!(serde_json::Value::from(stream_values(data, "a")?), expected);
+		assert_eq!(
+			serde_json::Value::from(stream_values(data, "$.a[1:3]")?),
+			serde_json::json!([-1.5e3, true])
+		);
+		assert_eq!(
+			serde_json::Value::from(stream_values(data, "$.a[4]")?),
+			serde_json::json!([null])
+		);
+
+		assert_eq!(stream_values("[1,\n2]", "")?, [1, 2]);
+		assert_eq!(stream_values(r#"{"a": [[1, 2], [3]]}"#, "a.*")?, [1, 2, 3]);
+		assert_eq!(stream_values(r#"{"a": [], "b": ["c"]}"#, "*")?, ["c"]);
+
+		let items = stream_read_items_at::<u32>(r#"[1, "2", 3]"#.bytes(), "");
+		let items = items.map(|item| item.ok()).collect::<Vec<_>>();
+		assert_eq!(items, [Some(1), None, Some(3)]);
+		Ok(())
+	}
+
+	thread_local! {
+		/// The number of bytes taken from the iterator given to [stream_read_items_at]
+		static TAKEN: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+	}
+
+	/// The number of bytes taken from the document when the first element of an array item was deserialized
+	struct TakenAtFirst(usize);
+
+	impl<'de> Deserialize<'de> for TakenAtFirst {
+		fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+			struct Visitor;
+			impl<'de> serde::de::Visitor<'de> for Visitor {
+				type Value = TakenAtFirst;
+				fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+					f.write_str("an array")
+				}
+				fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<TakenAtFirst, A::Error> {
+					seq.next_element::<serde::de::IgnoredAny>()?;
+					let taken = TAKEN.with(|taken| taken.get());
+					while seq.next_element::<serde::de::IgnoredAny>()?.is_some() {}
+					Ok(TakenAtFirst(taken))
+				}
+			}
+			deserializer.deserialize_seq(Visitor)
+		}
+	}
+
+	#[test]
+	fn stream_items_incrementally() -> TestResult {
+		let item = format!("[{}]", vec!["123456789"; 100_000].join(","));
+		let data = format!(r#"{{"a": [{}, {}, [1]]}}"#, item, item);
+		TAKEN.with(|taken| taken.set(0));
+		let bytes = data
+			.into_bytes()
+			.into_iter()
+			.inspect(|_| TAKEN.with(|taken| taken.set(taken.get() + 1)));
+		let items = stream_read_items_at::<TakenAtFirst>(bytes, "a").collect::<Result<Vec<_>, _>>()?;
+		// the items are deserialized while they are read, not once they are complete
+		assert_eq!(items.len(), 3);
+		assert!(items[0].0 < 100, "{}", items[0].0);
+		assert!(items[1].0 < item.len() + 100, "{}", items[1].0);
+		Ok(())
+	}
+
+	fn read_items<T: DeserializeOwned>(data: &str) -> Result<Vec<T>, JsonItError> {
+		JsonSeqIterator::new(data.as_bytes(), "a").collect()
+	}
+
+	#[test]
+	fn reader_item_types() -> TestResult {
+		for data in [r#"{"a":[-1,2,-30]}"#, "{\"a\": [ -1 ,\n\t2 ,-30\r\n] }"] {
+			assert_eq!(read_items::<i64>(data)?, [-1, 2, -30]);
+		}
+		assert_eq!(
+			read_items::<f64>(r#"{"a": [1.5, -2e3, 3E-2, 0.1e+1]}"#)?,
+			[1.5, -2e3, 3e-2, 1.0]
+		);
+		assert_eq!(read_items::<bool>(r#"{"a": [true, false,true]}"#)?, [true, false, true]);
+		assert_eq!(
+			read_items::<Option<u8>>(r#"{"a": [null, 1, null]}"#)?,
+			[None, Some(1), None]
+		);
+		assert_eq!(read_items::<String>(r#"{"a": ["x", "]\"", ""]}"#)?, ["x", "]\"", ""]);
+		assert_eq!(
+			read_items::<Vec<bool>>(r#"{"a": [[true], [], [false, true]]}"#)?,
+			[vec![true], vec![], vec![false, true]]
+		);
+		assert!(read_items::<u8>(r#"{"a": []}"#)?.is_empty());
+		let mixed = read_items::<serde_json::Value>(r#"{"a": [-1, true, false, null, "s", 1e2, [], {}]}"#)?;
+		assert_eq!(
+			serde_json::Value::from(mixed),
+			serde_json::json!([-1, true, false, null, "s", 1e2, [], {}])
+		);
+
+		// every item kind as the last one, with the rest of the document after it
+		for last in ["-1", "true", "false", "null", r#""s""#, "[1]", r#"{"b": 1}"#] {
+			let data = format!(r#"{{"a": [{}], "b": 2}}"#, last);
+			assert_eq!(read_items::<serde_json::Value>(&data)?.len(), 1, "{}", data);
+		}
+
+		// commas are only allowed between items
+		for data in [
+			r#"{"a": [1,,2]}"#,
+			r#"{"a": [1 2]}"#,
+			r#"{"a": [,1]}"#,
+			r#"{"a": [1,]}"#,
+			r#"{"a": [1,2,]}"#,
+			r#"{"a": [{} x {}]}"#,
+			r#"{"a": [1}]}"#,
+			r#"{"a": [tru]}"#,
+		] {
+			assert!(read_items::<serde_json::Value>(data).is_err(), "{}", data);
+			let items = stream_read_items_at::<serde_json::Value>(data.bytes(), "a");
+			assert!(items.collect::<Result<Vec<_>, _>>().is_err(), "{}", data);
+		}
+		let data = r#"{"a": {"x": 1,}}"#;
+		let iterator = JsonKvIterator::<_, u32>::new(data.as_bytes(), "a");
+		assert!(iterator.collect::<Result<Vec<_>, _>>().is_err());
+		Ok(())
+	}
+
+	/// Encodes a document in UTF-16 or UTF-32, with the byte order mark if asked
+	fn encode(data: &str, unit: usize, little_endian: bool, bom: bool) -> Vec<u8> {
+		let units: Vec<u32> = match unit {
+			2 => data.encode_utf16().map(u32::from).collect(),
+			_ => data.chars().map(u32::from).collect(),
+		};
+		let mut bytes = vec![];
+		for unit_value in bom.then_some(0xFEFF).into_iter().chain(units) {
+			let unit_bytes = if little_endian {
+				unit_value.to_le_bytes()
+			} else {
+				unit_value.to_be_bytes()
+			};
+			match (unit, little_endian) {
+				(2, true) => bytes.extend_from_slice(&unit_bytes[..2]),
+				(2, false) => bytes.extend_from_slice(&unit_bytes[2..]),
+				_ => bytes.extend_from_slice(&unit_bytes),
+			}
+		}
+		bytes
+	}
+
+	#[test]
+	fn encodings_and_whitespace() -> TestResult {
+		let data = "\r\n\t {\"caf\u{e9}\":\r\n\t[\"\u{1F600}\", \"x\"] }\n";
+		let expected = ["\u{1F600}", "x"];
+		let mut documents = vec![data.as_bytes().to_vec(), [b"\xEF\xBB\xBF", data.as_bytes()].concat()];
+		for unit in [2, 4] {
+			for little_endian in [true, false] {
+				for bom in [true, false] {
+					documents.push(encode(data, unit, little_endian, bom));
+				}
+			}
+		}
+		for document in documents {
+			let items = JsonSeqIterator::<_, String>::new(document.as_slice(), "café");
+			assert_eq!(items.collect::<Result<Vec<_>, _>>()?, expected, "{:?}", document);
+			let items = stream_read_items_at::<String>(&document, "café");
+			assert_eq!(items.collect::<Result<Vec<_>, _>>()?, expected, "{:?}", document);
+			assert_eq!(read_value_at::<String>(document.as_slice(), "café.1")?, "x");
+		}
+
+		// a single character document
+		assert_eq!(read_value_at::<u8>(encode("7", 2, true, false).as_slice(), "")?, 7);
+
+		// a form feed is not JSON whitespace
+		let error = read_items::<u8>("{\"a\":\x0C[1]}").unwrap_err();
+		assert!(
+			matches!(error, JsonItError::InvalidJsonCharacter { found: '\x0C', .. }),
+			"{}",
+			error
+		);
+
+		// a lone surrogate in UTF-16
+		let mut document = encode(r#"{"a": ["x"]}"#, 2, true, false);
+		document.splice(16..16, [0x00, 0xD8]);
+		let error = JsonSeqIterator::<_, String>::new(document.as_slice(), "a").find_map(Result::err);
+		assert_eq!(error.map(|e| e.kind()), Some(ErrorKind::Io));
+		let error = stream_read_items_at::<String>(document, "a").find_map(Result::err);
+		assert_eq!(error.map(|e| e.kind()), Some(ErrorKind::Io));
+		Ok(())
+	}
+
+	/// An unbuffered reader counting the calls to [Read::read]
+	struct CountingReader<'a> {
+		data: &'a [u8],
+		reads: usize,
+	}
+
+	impl Read for CountingReader<'_> {
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			self.reads += 1;
+			self.data.read(buf)
+		}
+	}
+
+	#[test]
+	fn reader_is_buffered() -> TestResult {
+		#[derive(Deserialize)]
+		struct Item {
+			id: usize,
+			name: String,
+		}
+		let skipped = (0..50_000)
+			.map(|i| format!(r#"{{"x": "{}"}}"#, i))
+			.collect::<Vec<_>>()
+			.join(",");
+		let items = (0..50_000)
+			.map(|i| format!(r#"{{"id": {}, "name": "item {}"}}"#, i, i))
+			.collect::<Vec<_>>()
+			.join(",");
+		let data = format!(r#"{{"skipped": [{}], "a": [{}]}}"#, skipped, items);
+		let mut reader = CountingReader {
+			data: data.as_bytes(),
+			reads: 0,
+		};
+		let mut count = 0;
+		for (i, item) in JsonSeqIterator::<_, Item>::new(&mut reader, "a").enumerate() {
+			let item = item?;
+			assert_eq!((item.id, item.name), (i, format!("item {}", i)));
+			count += 1;
+		}
+		assert_eq!(count, 50_000);
+		// at most one read per 16 KiB of document, whatever the reads of serde
+		assert!(reader.reads <= data.len() / (16 * 1024) + 2, "{} reads", reader.reads);
+
+		// also when the document is decoded from UTF-16
+		let document = encode(&data, 2, true, false);
+		let mut reader = CountingReader {
+			data: &document,
+			reads: 0,
+		};
+		assert_eq!(JsonSeqIterator::<_, Item>::new(&mut reader, "a").count(), 50_000);
+		assert!(
+			reader.reads <= document.len() / (16 * 1024) + 2,
+			"{} reads",
+			reader.reads
+		);
+		Ok(())
+	}
+
+	/// Streams a large document from an unbuffered [File], in UTF-8 and in UTF-16,
+	/// checking the throughput target of the reader.
+	/// Run with `cargo test --release -- --ignored reader_throughput`
+	#[test]
+	#[ignore]
+	fn reader_throughput() -> Result<(), Box<dyn Error>> {
+		/// In MB/s, of the document as given, in release builds
+		const TARGET: f64 = 20.0;
+		#[derive(Deserialize)]
+		struct Item {
+			id: usize,
+			#[allow(dead_code)]
+			tags: Vec<String>,
+		}
+		let skipped = r#"{"x": "skipped \"text\"", "y": [1, 2.5, null]}"#;
+		let item = r#"{"id": 0, "tags": ["lorem ipsum", "dolor sit amet"]}"#;
+		let data = format!(
+			r#"{{"skipped": [{}], "a": [{}]}}"#,
+			vec![skipped; 500_000].join(","),
+			vec![item; 500_000].join(",")
+		);
+		for (name, document) in [
+			("utf-8", data.clone().into_bytes()),
+			("utf-16", encode(&data, 2, true, false)),
+		] {
+			let path = std::env::temp_dir().join(format!("jsonit_throughput_{}.json", name));
+			std::fs::write(&path, &document)?;
+			let start = std::time::Instant::now();
+			let mut count = 0;
+			for item in JsonSeqIterator::<_, Item>::new(File::open(&path)?, "a") {
+				count += 1 + item?.id;
+			}
+			let throughput = document.len() as f64 / start.elapsed().as_secs_f64() / 1e6;
+			std::fs::remove_file(&path)?;
+			assert_eq!(count, 500_000);
+			assert!(throughput >= TARGET, "{}: {:.0} MB/s", name, throughput);
+			println!("{}: {:.0} MB/s", name, throughput);
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn search_skips_strings_across_buffers() -> TestResult {
+		// long strings with escapes, keys and whitespace across the chunks read from the reader
+		let text = format!("{}\\\"]}}{}\\\\", "lorem ipsum ".repeat(3000), "é ".repeat(5000));
+		let member = format!("\n\t\"te\\u0078t {}\" :\r\n  \"{}\" ,\n", text, text);
+		let data = format!(
+			r#"{{"skip": [{{{} "n": -12.5e3}}], {} "a\"b": [1, 2]}}"#,
+			member,
+			member.repeat(3)
+		);
+		let path = Path::root().key("a\"b");
+		let items = JsonSeqIterator::<_, u32>::new(data.as_bytes(), &path);
+		assert_eq!(items.collect::<Result<Vec<_>, _>>()?, [1, 2]);
+
+		// the location of an error is the one given by the iterator API, which reads every byte
+		let data = format!("{} x}}", &data[..data.len() - 1]);
+		let error = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "c")
+			.next()
+			.unwrap()
+			.unwrap_err();
+		let mut items = stream_read_items_at::<u32>(data.bytes(), "c");
+		let expected = items.next().unwrap().unwrap_err();
+		assert_eq!(error.location(), expected.location());
+		assert_eq!(error.location().map(|l| l.offset), Some(data.len() as u64 - 1));
+		Ok(())
+	}
+
+	#[test]
+	fn slice_iterator_borrows() -> TestResult {
+		#[derive(Deserialize, PartialEq, Debug)]
+		struct Borrowed<'a> {
+			name: &'a str,
+			#[serde(borrow)]
+			text: std::borrow::Cow<'a, str>,
+		}
+		let data = String::from(
+			"\u{FEFF}{\"skip\": \"[\\\"\", \"a\": [{\"name\": \"x\", \"text\": \"t\"}, {\"name\": \"y\", \"text\": \"\\\"q\\\"\"}]}",
+		);
+		let items = JsonSliceIterator::<Borrowed>::new(&data, "a").collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(items[0].name, "x");
+		assert!(matches!(items[0].text, std::borrow::Cow::Borrowed("t")));
+		assert_eq!(items[1].text, "\"q\"");
+		assert!(matches!(items[1].text, std::borrow::Cow::Owned(_)));
+		// the names point into the document
+		assert!(data.as_bytes().as_ptr_range().contains(&items[1].name.as_ptr()));
+
+		// the same items and errors as the reader
+		for (data, path) in [
+			(r#"{"a": [1, "x", [2], 3.5, null, 4]}"#, "a"),
+			(r#"{"p": [{"a": [1, 2]}, {"a": [3]}, {"a": 4}]}"#, "p.*.a"),
+			(r#"[[1], [2, 3], 4]"#, "$[1:]"),
+			(r#"{"a": [1, 2]}"#, "b"),
+			(r#"{"a": {"b": 1}}"#, "a"),
+			(r#"{"a": [1, 2, x]}"#, "a"),
+			(r#"{"a": [1 2]}"#, "a"),
+			(r#"{"a": [1, [2, [3]"#, "a"),
+			(r#"{"a": [1]}"#, "a[/"),
+			(r#"[true, nulll, false]"#, ""),
+			(r#"[true, 1x, false]"#, ""),
+		] {
+			let from_slice = JsonSliceIterator::<serde_json::Value>::new(data, path).collect::<Vec<_>>();
+			let from_reader = JsonSeqIterator::<_, serde_json::Value>::new(data.as_bytes(), path).collect::<Vec<_>>();
+			assert_eq!(
+				format!("{:?}", from_slice),
+				format!("{:?}", from_reader),
+				"{} at {}",
+				data,
+				path
+			);
+			let from_slice = JsonSliceIterator::<u32>::new(data, path).collect::<Vec<_>>();
+			let from_reader = JsonSeqIterator::<_, u32>::new(data.as_bytes(), path).collect::<Vec<_>>();
+			assert_eq!(from_slice.len(), from_reader.len(), "{} at {}", data, path);
+			for (a, b) in from_slice.iter().zip(&from_reader) {
+				assert_eq!(a.as_ref().ok(), b.as_ref().ok(), "{} at {}", data, path);
+				assert_eq!(a.as_ref().err().map(|e| e.kind()), b.as_ref().err().map(|e| e.kind()));
+				assert_eq!(
+					a.as_ref().err().map(|e| e.location()),
+					b.as_ref().err().map(|e| e.location())
+				);
+			}
+			let from_stream = stream_read_items_at::<u32>(data.bytes(), path).collect::<Vec<_>>();
+			assert_eq!(from_stream.len(), from_reader.len(), "{} at {}", data, path);
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn slice_iterator_many_errors() {
+		// each error is located from the previous one, not from the start of the document
+		let data = format!("{{\"a\": [{}]}}", vec!["\n\"x\""; 100_000].join(","));
+		let errors = JsonSliceIterator::<u32>::new(&data, "a")
+			.map(|item| item.unwrap_err().location().cloned())
+			.collect::<Vec<_>>();
+		assert_eq!(errors.len(), 100_000);
+		let last = errors[99_999].as_ref().unwrap();
+		assert_eq!((last.line, last.column, last.item), (100_001, 3, Some(99_999)));
+		let from_reader = JsonSeqIterator::<_, u32>::new(data.as_bytes(), "a")
+			.map(|item| item.unwrap_err().location().cloned())
+			.collect::<Vec<_>>();
+		assert_eq!(errors, from_reader);
+	}
+
+	#[cfg(feature = "mmap")]
+	#[test]
+	fn mmap_iterator() -> TestResult {
+		use jsonit::{JsonMmapIterator, Mmap};
+
+		// SAFETY: the fixtures are not modified by the tests
+		let items = unsafe { JsonMmapIterator::<Value>::from_path("./tests/test.json", "root.items")? };
+		let names = items
+			.map(|item| item.map(|item| item.name))
+			.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(names, ["hello1", "hello2"]);
+		let missing = unsafe { JsonMmapIterator::<Value>::from_path("./tests/missing.json", "root.items") };
+		assert!(missing.is_err());
+		// SAFETY: the fixtures are not modified by the tests
+		let items = unsafe { JsonMmapIterator::<Box<RawValue>>::from_path("./tests/simple.json", "a")? };
+		let items = items.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(
+			items.iter().map(|item| item.get()).collect::<Vec<_>>(),
+			[r#""deb""#, r#""sneb""#, "null"]
+		);
+
+		#[derive(Deserialize)]
+		struct Borrowed<'a> {
+			name: &'a str,
+		}
+		// SAFETY: the fixtures are not modified by the tests
+		let map = unsafe { Mmap::map(&File::open("./tests/test.json")?)? };
+		let names = JsonSliceIterator::<Borrowed>::new(&map, "root.items")
+			.map(|item| item.map(|item| item.name))
+			.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(names, ["hello1", "hello2"]);
+		let items = JsonMmapIterator::<u32>::from_mmap(map, "root.items").collect::<Vec<_>>();
+		assert_eq!(items.len(), 2);
+		assert!(items
+			.iter()
+			.all(|item| item.as_ref().is_err_and(|e| e.kind() == ErrorKind::Data)));
+		Ok(())
+	}
+
+	#[derive(Debug, PartialEq)]
+	enum Record {
+		User(String),
+		Order(u32),
+		Tag(String),
+	}
+
+	#[test]
+	fn reader_multi_path() -> TestResult {
+		let iterator = JsonMultiIterator::new(get_test_local_reader("./tests/multi.json"))
+			.with("orders", Record::Order)
+			.with("$..users[*]", |user: Value| Record::User(user.name))
+			.with("meta.tags", Record::Tag);
+		let records = iterator.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(
+			records,
+			[
+				Record::User("alice".to_owned()),
+				Record::User("bob".to_owned()),
+				Record::Tag("a".to_owned()),
+				Record::Tag("b".to_owned()),
+				Record::Order(10),
+				Record::Order(20),
+				Record::Order(30),
+				Record::User("carol".to_owned()),
+			]
+		);
+
+		// stops reading once every array is found
+		let data = r#"{"b": [1], "a": [2, 3], "c": "#;
+		let iterator = JsonMultiIterator::new(data.as_bytes())
+			.with("a", Record::Order)
+			.with(Path::root().key("b"), Record::Order);
+		assert_eq!(
+			iterator.collect::<Result<Vec<_>, _>>()?,
+			[Record::Order(1), Record::Order(2), Record::Order(3)]
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn reader_multi_path_invalid() {
+		let mut iterator = JsonMultiIterator::new(r#"{"a": [1]}"#.as_bytes())
+			.with("a", Record::Order)
+			.with("$[", Record::Order);
+		assert!(matches!(iterator.next(), Some(Err(JsonItError::InvalidPath(_)))));
+		assert!(iterator.next().is_none());
+
+		// only arrays are registered, whose items are deserialized whole
+		let data = r#"{"meta": {"n": 1}, "a": [{"b": [2]}]}"#;
+		let mut iterator = JsonMultiIterator::new(data.as_bytes())
+			.with("meta", Record::Order)
+			.with("a", |_: serde_json::Value| Record::Tag("a".to_owned()));
+		assert!(matches!(iterator.next(), Some(Err(JsonItError::NotAnArray { .. }))));
+		let mut iterator = JsonMultiIterator::new(data.as_bytes())
+			.with("a", |_: serde_json::Value| Record::Tag("a".to_owned()))
+			.with("a.0.b", Record::Order);
+		assert_eq!(iterator.next().unwrap().ok(), Some(Record::Tag("a".to_owned())));
+		assert!(matches!(iterator.next(), Some(Err(JsonItError::PathNotFound { .. }))));
+	}
+
+	#[test]
+	fn reader_kvitems() -> TestResult {
+		let data = r#"{
+			"count": 2,
+			"by_id": {"123": {"name": "a", "op": []}, "4\"56" : {"name": "b", "op": [{"a": "x"}]}},
+			"empty": {}
+		}"#;
+		let iterator = JsonSeqIterator::<_, Value>::kvitems(data.as_bytes(), "by_id");
+		let entries = iterator
+			.map(|e| e.map(|(k, v)| (k, v.name)))
+			.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(
+			entries,
+			[("123".to_owned(), "a".to_owned()), ("4\"56".to_owned(), "b".to_owned())]
+		);
+
+		let iterator = JsonKvIterator::<_, serde_json::Value>::new(data.as_bytes(), "empty");
+		assert_eq!(iterator.count(), 0);
+
+		let iterator = JsonKvIterator::<_, serde_json::Value>::new(data.as_bytes(), "");
+		let keys = iterator.map(|e| e.map(|(k, _)| k)).collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(keys, ["count", "by_id", "empty"]);
+
+		let data = r#"[{"a": 1, "b": 2}, [], {"c": 3}]"#;
+		let iterator = JsonKvIterator::<_, u32>::new(data.as_bytes(), "*");
+		let entries = iterator.collect::<Result<Vec<_>, _>>()?;
+		assert_eq!(entries, [("a".to_owned(), 1), ("b".to_owned(), 2), ("c".to_owned(), 3)]);
+		Ok(())
+	}
+
+	#[test]
+	fn reader_read_value_at() -> TestResult {
+		let data = r#"{"header": {"name": "h", "op": []}, "items": [1, 2, 3], "meta": {"total_count": 42}}"#;
+		assert_eq!(read_value_at::<u64>(data.as_bytes(), "meta.total_count")?, 42);
+		assert_eq!(read_value_at::<Value>(data.as_bytes(), "header")?.name, "h");
+		assert_eq!(read_value_at::<u32>(data.as_bytes(), "$.items[1]")?, 2);
+		assert_eq!(read_value_at::<Vec<u32>>(data.as_bytes(), "items")?, [1, 2, 3]);
+		assert_eq!(
+			read_value_at::<serde_json::Value>(r#" "root" "#.as_bytes(), "")?,
+			"root"
+		);
+
+		// stops reading once the value is found
+		let truncated = r#"{"count": 3, "items": [1, "#;
+		assert_eq!(read_value_at::<u32>(truncated.as_bytes(), "count")?, 3);
+
+		assert!(matches!(
+			read_value_at::<u32>(data.as_bytes(), "meta.missing"),
+			Err(JsonItError::PathNotFound { path, .. }) if path.to_string() == "meta.missing"
+		));
+		assert!(matches!(
+			read_value_at::<u32>(truncated.as_bytes(), "other"),
+			Err(JsonItError::PathNotFound { .. })
+		));
+		Ok(())
+	}
+
 	#[test]
 	fn reader_from_read_empty() -> TestResult {
 		test_read_with_type_at::<Value, _>(get_test_local_reader("./tests/test.json"), "empty")